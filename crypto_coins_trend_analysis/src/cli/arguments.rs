@@ -0,0 +1,341 @@
+use clap::{Args, Parser, Subcommand};
+use crypto_coins_trend_analysis::data_cleaning_load::period_config::{PeriodSpec, WindowSpec};
+use crypto_coins_trend_analysis::data_cleaning_load::data_loader::LoadOptions;
+use crypto_coins_trend_analysis::data_cleaning_load::graph_loader::InputFormat;
+use crypto_coins_trend_analysis::data_cleaning_load::sampling::Sampling;
+use crypto_coins_trend_analysis::data_cleaning_load::column_mapping::ColumnMapping;
+use crypto_coins_trend_analysis::data_cleaning_load::validation::ValidationMode;
+use crypto_coins_trend_analysis::data_cleaning_load::token_registry::TokenRegistry;
+use crypto_coins_trend_analysis::data_cleaning_load::price_table::{PriceTable, ValueBasis};
+use crypto_coins_trend_analysis::computation_analysis::computation_algorithms::{
+    BetweennessWeighting, CentralityOptions, ClosenessVariant, SourceSample, TOP_NODES,
+};
+use crypto_coins_trend_analysis::graph::transaction_graph::EdgeCounting;
+use crypto_coins_trend_analysis::utility::edge_cost::EdgeCost;
+use crypto_coins_trend_analysis::utility::helper_algorithm::PathDirection;
+use crypto_coins_trend_analysis::reporting::analysis_report::OutputFormat;
+use crypto_coins_trend_analysis::reporting::graph_export::{GraphFormat, Subgraph};
+
+//Cli, Command, InputArgs, CentralityArgs
+
+/**************************************************************
+*
+*   The command line of the project, one subcommand per analysis
+*   so only the needed piece is loaded and computed
+*
+***************************************************************/
+
+#[derive(Debug, Parser)]
+#[command(version, about = "Transaction graph analysis of ERC20 stablecoin transfers")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Load the transfers and print the load summary, address counts and density of every period
+    #[command(visible_alias = "stats")]
+    Load {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Compare the tokens across the periods
+        #[arg(long)]
+        by_token: bool,
+        /// Print every edge of the period graphs
+        #[arg(long)]
+        show_edges: bool,
+    },
+    /// The density of every period graph
+    Density {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// The in, out and total degree and the strength distributions
+    Degrees {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Degree, closeness and betweenness centrality with the top addresses
+    Centrality {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        centrality: CentralityArgs,
+        /// Number of top addresses listed per measure
+        #[arg(long, default_value_t = TOP_NODES)]
+        top: usize,
+    },
+    /// Weakly and strongly connected components
+    Components {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Number of largest components whose members are listed
+        #[arg(long, default_value_t = 3)]
+        top: usize,
+    },
+    /// Shortest paths from an address, to another one or to the nearest addresses it reaches
+    Paths {
+        #[command(flatten)]
+        input: InputArgs,
+        /// The sending address
+        #[arg(long)]
+        from: String,
+        /// The receiving address, the nearest addresses when not given
+        #[arg(long)]
+        to: Option<String>,
+        /// Edge cost: value, hops, inverse-value, neg-log-share or time-delay
+        #[arg(long, default_value = "value")]
+        cost: EdgeCost,
+        /// Number of nearest addresses listed without --to
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// A time series over sliding or tumbling windows such as 6h:1h covering the periods
+    Windows {
+        /// Window width and step, e.g. 6h:1h (units s, m, h, d, w)
+        windows: WindowSpec,
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Write the period graphs into the output directory as edge lists, GraphML, GEXF or DOT
+    Export {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Graph formats, comma separated: edges, graphml, gexf, dot
+        #[arg(long, value_delimiter = ',', default_value = "edges")]
+        graph_format: Vec<GraphFormat>,
+        /// full, largest-component or hubs:<k> for the ego networks of the k highest degree addresses
+        #[arg(long, default_value = "full")]
+        subgraph: Subgraph,
+        /// Add the closeness and betweenness of every address to the node attributes
+        #[arg(long)]
+        with_centrality: bool,
+        #[command(flatten)]
+        centrality: CentralityArgs,
+    },
+    /// Every analysis of the periods, the full report
+    All {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        centrality: CentralityArgs,
+        /// Compare the tokens across the periods and analyse every token subgraph
+        #[arg(long)]
+        by_token: bool,
+        /// Number of top addresses listed per centrality measure
+        #[arg(long, default_value_t = TOP_NODES)]
+        top: usize,
+        /// Number of largest components whose members are listed
+        #[arg(long, default_value_t = 3)]
+        top_components: usize,
+    },
+}
+
+/**************************************************************
+*
+*   The arguments shared by every subcommand: the input file, the
+*   periods, how the transfers are loaded and filtered, and where
+*   the results go
+*
+***************************************************************/
+
+#[derive(Debug, Args)]
+pub struct InputArgs {
+    /// The transfer export, a csv file that may be gzip or zstd compressed, or a graph file (see --input-format)
+    pub input: String,
+    /// transfers, edges, graphml, jsonl or parquet, guessed from the file extension when not given
+    #[arg(long)]
+    pub input_format: Option<InputFormat>,
+    /// A TOML or JSON file of [[periods]] with name, start and end
+    #[arg(long, conflicts_with = "period")]
+    pub periods: Option<String>,
+    /// A period name:start:end, repeatable, the LUNA crash periods when none is given
+    #[arg(long)]
+    pub period: Vec<String>,
+    /// Only the given tokens, by symbol or contract address, repeatable
+    #[arg(long)]
+    pub token: Vec<String>,
+    /// Count every transfer instead of distinct neighbours
+    #[arg(long)]
+    pub transfers: bool,
+    /// all, first:<n>, reservoir:<n> or daily:<n>
+    #[arg(long, default_value = "all")]
+    pub sample: Sampling,
+    /// Seed of the random samples
+    #[arg(long, default_value_t = 42)]
+    pub seed: u64,
+    /// Rows read at a time
+    #[arg(long, default_value_t = 100_000)]
+    pub chunk_size: usize,
+    /// Hide the loading progress
+    #[arg(long)]
+    pub quiet: bool,
+    /// Column preset chartalist, etherscan, bigquery or dune, or a .toml/.json mapping file
+    #[arg(long, default_value = "chartalist")]
+    pub columns: String,
+    /// Drop invalid rows instead of aborting on the first one
+    #[arg(long)]
+    pub validate: bool,
+    /// Also write the invalid rows with their reason to this file
+    #[arg(long)]
+    pub quarantine: Option<String>,
+    /// A TOML or JSON token registry added to the built-in tokens
+    #[arg(long)]
+    pub tokens: Option<String>,
+    /// Divide raw on-chain amounts by 10^decimals of their token, for exports such as bigquery,
+    /// the chartalist values are already in whole tokens
+    #[arg(long)]
+    pub normalize_values: bool,
+    /// A CSV of token,timestamp,price_usd to value the transfers in USD
+    #[arg(long)]
+    pub prices: Option<String>,
+    /// token or usd
+    #[arg(long, default_value = "token")]
+    pub value_basis: ValueBasis,
+    /// Output formats, comma separated: console, json, csv
+    #[arg(long, value_delimiter = ',', default_value = "console")]
+    pub format: Vec<OutputFormat>,
+    /// Directory of the json and csv results and the exported graphs
+    #[arg(long, default_value = "results")]
+    pub output: String,
+    /// Reuse the graphs of this binary snapshot, it is rebuilt from the input when missing or stale
+    #[arg(long)]
+    pub snapshot: Option<String>,
+}
+
+impl InputArgs {
+    pub fn counting(&self) -> EdgeCounting {
+        if self.transfers {
+            EdgeCounting::Transfers
+        } else {
+            EdgeCounting::Distinct
+        }
+    }
+
+    pub fn input_format(&self) -> InputFormat {
+        self.input_format.unwrap_or_else(|| InputFormat::from_path(&self.input))
+    }
+
+    pub fn period_spec(&self) -> Result<PeriodSpec, Box<dyn std::error::Error>> {
+        match (&self.periods, self.period.is_empty()) {
+            (Some(file), _) => PeriodSpec::from_file(file),
+            (None, false) => PeriodSpec::from_args(&self.period),
+            (None, true) => Ok(PeriodSpec::luna_crash()),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   The options that change the loaded graphs, a snapshot built
+    *   with other settings is rebuilt. The mapping, registry and
+    *   price files are recorded with the crc32 of their content so
+    *   editing one of them makes the snapshot stale
+    *
+    ***************************************************************/
+
+    pub fn snapshot_settings(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let file = |path: Option<&String>| -> Result<String, std::io::Error> {
+            match path {
+                Some(path) => Ok(format!("{}#{:08x}", path, crc32fast::hash(&std::fs::read(path)?))),
+                None => Ok("-".to_string()),
+            }
+        };
+        let columns = if self.columns.ends_with(".toml") || self.columns.ends_with(".json") {
+            file(Some(&self.columns))?
+        } else {
+            self.columns.clone()
+        };
+        Ok(format!(
+            "format={:?} sample={:?} seed={} columns={} validate={} tokens={} normalize_values={} prices={} value_basis={:?}",
+            self.input_format(),
+            self.sample,
+            self.seed,
+            columns,
+            self.validate || self.quarantine.is_some(),
+            file(self.tokens.as_ref())?,
+            self.normalize_values,
+            file(self.prices.as_ref())?,
+            self.value_basis
+        ))
+    }
+
+    /**************************************************************
+    *
+    *   The loader options, the mapping, registry and price files
+    *   are read here
+    *
+    ***************************************************************/
+
+    pub fn load_options(&self) -> Result<LoadOptions, Box<dyn std::error::Error>> {
+        let mut load = LoadOptions {
+            sampling: self.sample,
+            seed: self.seed,
+            chunk_size: self.chunk_size,
+            progress: !self.quiet,
+            normalize_values: self.normalize_values,
+            value_basis: self.value_basis,
+            ..LoadOptions::default()
+        };
+        load.columns = if self.columns.ends_with(".toml") || self.columns.ends_with(".json") {
+            ColumnMapping::from_file(&self.columns)?
+        } else {
+            ColumnMapping::preset(&self.columns)?
+        };
+        load.validation.mode = match (&self.quarantine, self.validate) {
+            (Some(path), _) => ValidationMode::Quarantine(path.clone()),
+            (None, true) => ValidationMode::Skip,
+            (None, false) => ValidationMode::Off,
+        };
+        if let Some(path) = &self.tokens {
+            load.tokens = TokenRegistry::from_file(path)?;
+        }
+        if let Some(path) = &self.prices {
+            load.prices = PriceTable::from_csv(path)?;
+        }
+        Ok(load)
+    }
+}
+
+/**************************************************************
+*
+*   The options of the closeness and betweenness computations
+*
+***************************************************************/
+
+#[derive(Debug, Args)]
+pub struct CentralityArgs {
+    /// Edge cost of the shortest paths: value, hops, inverse-value, neg-log-share or time-delay
+    #[arg(long, default_value = "value")]
+    pub cost: EdgeCost,
+    /// classic, harmonic or wasserman-faust
+    #[arg(long, default_value = "harmonic")]
+    pub closeness: ClosenessVariant,
+    /// out or in
+    #[arg(long, default_value = "out")]
+    pub closeness_direction: PathDirection,
+    /// weighted or unweighted
+    #[arg(long, default_value = "weighted")]
+    pub betweenness: BetweennessWeighting,
+    /// Approximate betweenness from this many random source addresses
+    #[arg(long)]
+    pub betweenness_sample: Option<usize>,
+    /// Worker threads, all cores when not given
+    #[arg(long)]
+    pub threads: Option<usize>,
+}
+
+impl CentralityArgs {
+    pub fn options(&self, input: &InputArgs, top_nodes: usize) -> CentralityOptions {
+        CentralityOptions {
+            counting: input.counting(),
+            cost: self.cost,
+            closeness: self.closeness,
+            closeness_direction: self.closeness_direction,
+            betweenness: self.betweenness,
+            sample: self.betweenness_sample.map(|sources| SourceSample { sources, seed: input.seed }),
+            threads: self.threads,
+            top_nodes,
+        }
+    }
+}
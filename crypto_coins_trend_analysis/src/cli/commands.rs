@@ -0,0 +1,183 @@
+use std::path::Path;
+use crate::cli::arguments::{Command, InputArgs};
+use crypto_coins_trend_analysis::data_cleaning_load::data_loader::{self, PeriodGraphs};
+use crypto_coins_trend_analysis::data_cleaning_load::graph_loader::{self, InputFormat};
+use crypto_coins_trend_analysis::data_cleaning_load::period_config::PeriodSpec;
+use crypto_coins_trend_analysis::data_cleaning_load::sampling::Sampling;
+use crypto_coins_trend_analysis::computation_analysis::{computation_algorithms, token_analysis, temporal_analysis, path_analysis};
+use crypto_coins_trend_analysis::connected_component::find_connected_component;
+use crypto_coins_trend_analysis::reporting::analysis_report::AnalysisReport;
+use crypto_coins_trend_analysis::reporting::graph_export::{self, ExportOptions};
+use crypto_coins_trend_analysis::computation_analysis::computation_algorithms::TOP_NODES;
+use crypto_coins_trend_analysis::graph::graph_snapshot::{self, Snapshot, SnapshotError, SourceStamp};
+
+//run
+
+/**************************************************************
+*
+*   Helper function to parse the input into the graph of every
+*   period with the loader of its format and print the load summary
+*
+***************************************************************/
+
+fn load_input(input: &InputArgs, spec: &PeriodSpec) -> Result<PeriodGraphs, Box<dyn std::error::Error>> {
+    let loader = graph_loader::loader_for(input.input_format(), &input.input)?;
+    let (graphs, summary) = loader.load_periods(spec, &input.load_options()?)?;
+    println!("finished loading graph......");
+    summary.display();
+    Ok(graphs)
+}
+
+/**************************************************************
+*
+*   Helper function to reuse the snapshot when it was built from
+*   the same input, periods and settings, otherwise the input is
+*   parsed again and the snapshot replaced
+*
+***************************************************************/
+
+fn load_snapshot_or_csv(input: &InputArgs, spec: &PeriodSpec, path: &Path) -> Result<PeriodGraphs, Box<dyn std::error::Error>> {
+    let source = SourceStamp::of(&input.input)?;
+    let settings = input.snapshot_settings()?;
+    match graph_snapshot::load_snapshot(path) {
+        Ok(snapshot) if snapshot.matches(source, &settings, spec) => {
+            println!("loaded snapshot {}......", path.display());
+            return Ok(snapshot.graphs);
+        }
+        Ok(_) => println!("snapshot {} is stale, rebuilding......", path.display()),
+        Err(SnapshotError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => println!("snapshot {} is unusable ({}), rebuilding......", path.display(), e),
+    }
+    let graphs = load_input(input, spec)?;
+    let snapshot = Snapshot { source, settings, periods: spec.clone(), graphs };
+    graph_snapshot::save_snapshot(path, &snapshot)?;
+    println!("wrote snapshot {}", path.display());
+    Ok(snapshot.graphs)
+}
+
+/**************************************************************
+*
+*   Helper function to load the graph of every period, from the
+*   snapshot when one is given, and keep only the selected tokens
+*
+***************************************************************/
+
+fn load_graphs(input: &InputArgs, spec: &PeriodSpec) -> Result<PeriodGraphs, Box<dyn std::error::Error>> {
+    let graphs = match &input.snapshot {
+        Some(path) => load_snapshot_or_csv(input, spec, Path::new(path))?,
+        None => load_input(input, spec)?,
+    };
+    if input.token.is_empty() {
+        Ok(graphs)
+    } else {
+        Ok(token_analysis::filter_by_tokens(&graphs, &input.token))
+    }
+}
+
+/**************************************************************
+*
+*   Helper function to render the report in the selected formats
+*   and list the files that were written
+*
+***************************************************************/
+
+fn render(report: &AnalysisReport, input: &InputArgs) -> Result<(), Box<dyn std::error::Error>> {
+    for path in report.render(&input.format, Path::new(&input.output))? {
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+/**************************************************************
+*
+*   Run one subcommand, only the analyses it names are computed
+*
+***************************************************************/
+
+pub fn run(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Load { input, by_token, show_edges } => {
+            let graphs = load_graphs(&input, &input.period_spec()?)?;
+            let tokens = if by_token { token_analysis::compare_tokens(&graphs, &[], input.counting()) } else { Vec::new() };
+            if show_edges {
+                for (period, graph) in &graphs {
+                    data_loader::display_graph(graph, &format!("Transaction Graph [{}]", period));
+                }
+            }
+            let graphs = computation_algorithms::summarize_graphs(&graphs, input.counting(), false);
+            render(&AnalysisReport { graphs, tokens, ..AnalysisReport::default() }, &input)
+        }
+        Command::Density { input } => {
+            let graphs = load_graphs(&input, &input.period_spec()?)?;
+            let graphs = computation_algorithms::summarize_graphs(&graphs, input.counting(), false);
+            render(&AnalysisReport { graphs, ..AnalysisReport::default() }, &input)
+        }
+        Command::Degrees { input } => {
+            let graphs = load_graphs(&input, &input.period_spec()?)?;
+            let graphs = computation_algorithms::summarize_graphs(&graphs, input.counting(), true);
+            render(&AnalysisReport { graphs, ..AnalysisReport::default() }, &input)
+        }
+        Command::Centrality { input, centrality, top } => {
+            let graphs = load_graphs(&input, &input.period_spec()?)?;
+            let centrality = computation_algorithms::centrality_reports(&graphs, &centrality.options(&input, top));
+            render(&AnalysisReport { centrality, ..AnalysisReport::default() }, &input)
+        }
+        Command::Components { input, top } => {
+            let graphs = load_graphs(&input, &input.period_spec()?)?;
+            let components = find_connected_component::component_reports(&graphs, top);
+            render(&AnalysisReport { components, ..AnalysisReport::default() }, &input)
+        }
+        Command::Paths { input, from, to, cost, top } => {
+            let graphs = load_graphs(&input, &input.period_spec()?)?;
+            let paths = path_analysis::path_reports(&graphs, &from, to.as_deref(), cost, input.counting(), top);
+            if paths.is_empty() {
+                println!("no path from {} found in any period", from);
+            }
+            render(&AnalysisReport { paths, ..AnalysisReport::default() }, &input)
+        }
+        Command::Windows { windows, input } => {
+            let (start, end) = input.period_spec()?.range();
+            let spec = PeriodSpec::windows(start, end, &windows)?;
+            let load = input.load_options()?;
+            // Sampling is per window, so only the unsampled time series slides one graph over the transfer csv,
+            // with a snapshot the window graphs are read from or saved to it instead
+            let time_series = if load.sampling == Sampling::All && input.input_format() == InputFormat::Transfers && input.snapshot.is_none() {
+                let (series, summary) = temporal_analysis::load_time_series(&input.input, &spec, &load, &input.token, input.counting())?;
+                summary.display();
+                series
+            } else {
+                temporal_analysis::compute_time_series(&load_graphs(&input, &spec)?, &spec, input.counting())
+            };
+            render(&AnalysisReport { time_series, ..AnalysisReport::default() }, &input)
+        }
+        Command::Export { input, graph_format, subgraph, with_centrality, centrality } => {
+            let graphs = load_graphs(&input, &input.period_spec()?)?;
+            let options = ExportOptions {
+                formats: graph_format,
+                subgraph,
+                counting: input.counting(),
+                centrality: with_centrality.then(|| centrality.options(&input, TOP_NODES)),
+            };
+            for path in graph_export::export_graphs_with(&graphs, Path::new(&input.output), &options)? {
+                println!("wrote {}", path.display());
+            }
+            Ok(())
+        }
+        Command::All { input, centrality, by_token, top, top_components } => {
+            let mut graphs = load_graphs(&input, &input.period_spec()?)?;
+            let mut tokens = Vec::new();
+            if by_token {
+                tokens = token_analysis::compare_tokens(&graphs, &[], input.counting());
+                graphs = token_analysis::split_by_token(&graphs, &[]);
+            }
+            let report = AnalysisReport {
+                graphs: computation_algorithms::summarize_graphs(&graphs, input.counting(), true),
+                centrality: computation_algorithms::centrality_reports(&graphs, &centrality.options(&input, top)),
+                components: find_connected_component::component_reports(&graphs, top_components),
+                tokens,
+                ..AnalysisReport::default()
+            };
+            render(&report, &input)
+        }
+    }
+}
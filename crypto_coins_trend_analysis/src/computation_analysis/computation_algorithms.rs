@@ -1,330 +1,894 @@
-use std::collections::BinaryHeap;
-use std::collections::HashMap;
-use std::collections::HashSet;
-use crate::data_loader::Transaction;
-use crate::utility::helper_algorithm::dijkstra;
-use crate::helper_algorithm::Distance;
-
-//compute_density(), compute_degree_distribution, all_shortest_paths, compute_centrality, 
-//normalize_degree, normalize_betweenness, compute_statistics, find_top_nodes, analyze_centrality_across_periods
-
-/************************************************
-*
-*   Compute the Density of given graph
-*
-*************************************************/
-
-pub fn compute_density(graph: &HashMap<String, HashMap<String, Transaction>>) -> f64 {
-    let num_nodes = graph.len();
-    let num_edges: usize = graph.values().map(|edges| edges.len()).sum();
-  
-    if num_nodes < 2 {
-        return 0.0; // A single-node or empty graph has no density
-    }
-  
-    num_edges as f64 / (num_nodes as f64 * (num_nodes as f64 - 1.0))
-}
-
-/**************************************************************
-*
-*   Compute the Gegree Distribution of given graph
-*
-***************************************************************/
-
-pub fn compute_degree_distribution(graph: &HashMap<String, HashMap<String, Transaction>>) -> HashMap<usize, usize> {
-    let mut degree_count: HashMap<usize, usize> = HashMap::new();
-  
-    for node in graph.keys() {
-        let degree = graph.get(node).map_or(0, |edges| edges.len());
-        *degree_count.entry(degree).or_insert(0) += 1;
-    }
-  
-    degree_count
-}
-
-/**************************************************************
-*
-*   Wrapper Function Call From main.rs
-*   compute the density and degree distribution of three graphs:
-*   Before, During, After LUNA crashes
-*
-***************************************************************/
-
-pub fn analyze_graphs(
-    graph_before: &HashMap<String, HashMap<String, Transaction>>,
-    graph_during: &HashMap<String, HashMap<String, Transaction>>,
-    graph_after: &HashMap<String, HashMap<String, Transaction>>,
-) {
-    // Compute density for each graph
-    let density_before = compute_density(graph_before);
-    let density_during = compute_density(graph_during);
-    let density_after = compute_density(graph_after);
-
-    println!("Graph Density (Before, During, After): {:?}, {:?}, {:?}", density_before, density_during, density_after);
-
-    // Compute degree distribution for each graph
-    let degree_dist_before = compute_degree_distribution(graph_before);
-    let degree_dist_during = compute_degree_distribution(graph_during);
-    let degree_dist_after = compute_degree_distribution(graph_after);
-
-    println!("\nDegree Distribution (Before): {:?}", degree_dist_before);
-    println!("Degree Distribution (During): {:?}", degree_dist_during);
-    println!("Degree Distribution (After): {:?}", degree_dist_after);
-
-}
-
-/**************************************************************
-*
-*   Compute the degree centrality and closeness centrality of 
-*   given graph
-*
-***************************************************************/
-
-pub fn compute_centrality(
-    graph: &HashMap<String, HashMap<String, Transaction>>,
-  ) -> (HashMap<String, usize>, HashMap<String, f64>) {
-    let mut degree_centrality: HashMap<String, usize> = HashMap::new();
-    let mut closeness_centrality: HashMap<String, f64> = HashMap::new();
-  
-    // Degree Centrality
-    for node in graph.keys() {
-        let degree = graph.get(node).map_or(0, |edges| edges.len());
-        degree_centrality.insert(node.clone(), degree);
-    }
-
-    println!("finished computation of one graph's degree centrality......");
-  
-    // Closeness Centrality
-    for node in graph.keys() {
-        let shortest_paths = dijkstra(graph, node);
-        let total_distance: f64 = shortest_paths.values().sum();
-        let closeness = if total_distance > 0.0 {
-            (graph.len() - 1) as f64 / total_distance
-        } else {
-            0.0
-        };
-        closeness_centrality.insert(node.clone(), closeness);
-    }
-    
-    println!("finished computation of one graph's closeness centrality......");
-    (degree_centrality, closeness_centrality)
-}
-
-/**************************************************************
-*
-*   Helper function to normalize the centrality metrics, which
-*   normalizes the degree centrality
-*
-***************************************************************/
-
-pub fn normalize_degree(centrality: &HashMap<String, usize>, max_possible_degree: usize) -> HashMap<String, f64> {
-    centrality.iter()
-        .map(|(node, &degree)| (node.clone(), degree as f64 / max_possible_degree as f64))
-        .collect()
-}
-
-/**************************************************************
-*
-*   Helper function to calculate statistics of centrality metrics,
-*   the mean and variance of given centrality metric
-*
-***************************************************************/
-
-pub fn compute_statistics(centrality: &HashMap<String, f64>) -> (f64, f64) {
-    let n = centrality.len() as f64;
-    let mean = centrality.values().sum::<f64>() / n;
-    let variance = centrality.values().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
-    (mean, variance)
-}
-
-/**************************************************************
-*
-*   Helper function to find Top Degree Nodes (Most Connected Addresses)
-*   from given degree centrality metric(normalized)
-*
-***************************************************************/
-
-pub fn find_top_nodes(centrality: &HashMap<String, f64>, top_n: usize) -> Vec<(String, f64)> {
-    let mut centrality_vec: Vec<_> = centrality.iter().collect();
-    centrality_vec.sort_by(|a, b| b.1.partial_cmp(a.1).unwrap());
-    centrality_vec.into_iter().take(top_n).map(|(k, v)| (k.clone(), *v)).collect()
-}
-
-/**************************************************************
-*
-*   The wrapper function that encompass all the analysis part for convenience
-*   to be called by main.rs
-*
-***************************************************************/
-
-pub fn analyze_centrality_across_periods(
-  graph_before: &HashMap<String, HashMap<String, Transaction>>,
-  graph_during: &HashMap<String, HashMap<String, Transaction>>,
-  graph_after: &HashMap<String, HashMap<String, Transaction>>,
-) {
-    // Compute centralities for each period
-    let (degree_before, closeness_before) = compute_centrality(graph_before);
-    let (degree_during, closeness_during) = compute_centrality(graph_during);
-    let (degree_after, closeness_after) = compute_centrality(graph_after);
-
-    // Normalize centrality metrics
-    let norm_degree_before = normalize_degree(&degree_before, graph_before.len() - 1);
-    let norm_degree_during = normalize_degree(&degree_during, graph_during.len() - 1);
-    let norm_degree_after = normalize_degree(&degree_after, graph_after.len() - 1);
-
-
-    // Compute summary statistics
-    let (mean_degree_before, var_degree_before) = compute_statistics(&norm_degree_before);
-    let (mean_degree_during, var_degree_during) = compute_statistics(&norm_degree_during);
-    let (mean_degree_after, var_degree_after) = compute_statistics(&norm_degree_after);
-    println!("=== Centrality Analysis ===");
-    println!("Degree Centrality Mean (Before, During, After): {:?}, {:?}, {:?}", mean_degree_before, mean_degree_during, mean_degree_after);
-    println!("Degree Centrality Variance (Before, During, After): {:?}, {:?}, {:?}", var_degree_before, var_degree_during, var_degree_after);
-
-    // Identify top nodes
-    let top_degree_before = find_top_nodes(&norm_degree_before, 5);
-    let top_degree_during = find_top_nodes(&norm_degree_during, 5);
-    let top_degree_after = find_top_nodes(&norm_degree_after, 5);
-    println!("\nTop Degree Nodes (Before): {:?}", top_degree_before);
-    println!("Top Degree Nodes (During): {:?}", top_degree_during);
-    println!("Top Degree Nodes (After): {:?}", top_degree_after);
-
-/*
-  let betweenness_before = compute_betweeness_centrality(graph_before);
-  let betweenness_during = compute_betweeness_centrality(graph_during);
-  let betweenness_after = compute_betweeness_centrality(graph_after);
-
-  let norm_betweenness_before = normalize_betweenness(&betweenness_before, graph_before.len());
-  let norm_betweenness_during = normalize_betweenness(&betweenness_during, graph_during.len());
-  let norm_betweenness_after = normalize_betweenness(&betweenness_after, graph_after.len());
-
-
-  let (mean_betweenness_before, var_betweenness_before) = compute_statistics(&norm_betweenness_before);
-  let (mean_betweenness_during, var_betweenness_during) = compute_statistics(&norm_betweenness_during);
-  let (mean_betweenness_after, var_betweenness_after) = compute_statistics(&norm_betweenness_after);
-  println!("Betweenness Centrality Mean (Before, During, After): {:.2}, {:.2}, {:.2}", mean_betweenness_before, mean_betweenness_during, mean_betweenness_after);
-  println!("Betweenness Centrality Variance (Before, During, After): {:.2}, {:.2}, {:.2}", var_betweenness_before, var_betweenness_during, var_betweenness_after);
-
-  let top_betweenness_before = find_top_nodes(&norm_betweenness_before, 5);
-  let top_betweenness_during = find_top_nodes(&norm_betweenness_during, 5);
-  let top_betweenness_after = find_top_nodes(&norm_betweenness_after, 5);
-
-  println!("\nTop Betweenness Nodes (Before): {:?}", top_betweenness_before);
-  println!("Top Betweenness Nodes (During): {:?}", top_betweenness_during);
-  println!("Top Betweenness Nodes (After): {:?}", top_betweenness_after);
-*/
-}
-
-/*
-/**************************************************************
-*
-*   Helper function used for computation of betweeness centrality,
-*   which finds all shortest path from given node in the graph
-*
-***************************************************************/
-pub fn all_shortest_paths(
-    graph: &HashMap<String, HashMap<String, Transaction>>,
-    start: &str,
-) -> HashMap<String, Vec<Vec<String>>> {
-    let mut shortest_paths: HashMap<String, Vec<Vec<String>>> = HashMap::new();
-    let mut distances: HashMap<String, Distance> = HashMap::new();
-    let mut heap = BinaryHeap::new();
-
-    distances.insert(start.to_string(), Distance(0.0));
-    heap.push(std::cmp::Reverse((Distance(0.0), start.to_string())));
-
-    while let Some(std::cmp::Reverse((dist, current))) = heap.pop() {
-        if dist > *distances.get(&current).unwrap_or(&Distance(f64::INFINITY)) {
-            continue;
-        }
-
-        if !shortest_paths.contains_key(&current) {
-            shortest_paths.insert(current.clone(), vec![vec![start.to_string()]]);
-        }
-
-        if let Some(neighbors) = graph.get(&current) {
-            for (neighbor, transaction) in neighbors {
-                let new_dist = Distance(dist.0 + transaction.value);
-
-                if new_dist < *distances.get(neighbor).unwrap_or(&Distance(f64::INFINITY)) {
-                    distances.insert(neighbor.clone(), new_dist);
-
-                    let current_paths = shortest_paths.get(&current).unwrap().clone(); // Store the current paths
-                    shortest_paths.insert(neighbor.clone(), current_paths);
-
-                    if let Some(paths) = shortest_paths.get_mut(neighbor) {
-                        for path in paths.iter_mut() {
-                            path.push(neighbor.clone());
-                        }
-                    }
-
-                    heap.push(std::cmp::Reverse((new_dist, neighbor.clone())));
-                } else if new_dist == *distances.get(neighbor).unwrap_or(&Distance(f64::INFINITY)) {
-                    let current_paths = shortest_paths.get(&current).unwrap().clone(); // Store the current paths
-
-                    if let Some(paths) = shortest_paths.get_mut(neighbor) {
-                        for mut path in current_paths {
-                            path.push(neighbor.clone());
-                            paths.push(path);
-                        }
-                    }
-                }
-            }
-        }
-    }
-    shortest_paths
-}
-
-/**************************************************************
-*
-*   Compute the betweeness centrality of a graph, which is a
-*   measure of centrality in a graph based on shortest paths.
-*
-***************************************************************/
-
-pub fn compute_betweeness_centrality(
-    graph: &HashMap<String, HashMap<String, Transaction>>
-) -> HashMap<String, f64>{
-    //betweeness centrality
-    let mut betweenness_centrality: HashMap<String, f64> = HashMap::new();
-
-    for source in graph.keys() {
-        let all_paths_from_source = all_shortest_paths(graph, source);
-
-        for target in graph.keys() {
-            if source == target {
-                continue;
-            }
-
-            if let Some(paths) = all_paths_from_source.get(target) {
-                for path in paths {
-                    for node in path.iter().skip(1).take(path.len() - 2) { // Exclude source and target
-                        *betweenness_centrality.entry(node.clone()).or_insert(0.0) += 1.0 / paths.len() as f64;
-                    }
-                }
-            }
-        }
-    }
-
-    // Normalize betweenness centrality
-    let n = graph.len() as f64;
-    for centrality in betweenness_centrality.values_mut() {
-        *centrality /= (n - 1.0) * (n - 2.0);
-    }
-    
-    betweenness_centrality
-}
-
-/**************************************************************
-*
-*   Normalizes the result after computed the betweeness centrality
-*
-***************************************************************/
-
-fn normalize_betweenness(centrality: &HashMap<String, f64>, n: usize) -> HashMap<String, f64> {
-    let max_possible = (n - 1) as f64 * (n - 2) as f64;
-    centrality.iter()
-        .map(|(node, &value)| (node.clone(), value / max_possible))
-        .collect()
-}
-*/
\ No newline at end of file
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, VecDeque};
+use rand::rngs::StdRng;
+use rand::seq::index;
+use rand::SeedableRng;
+use crate::data_cleaning_load::data_loader::PeriodGraphs;
+use crate::graph::transaction_graph::{TransactionGraph, NodeId, EdgeCounting, NodeCounts};
+use crate::utility::helper_algorithm::{dijkstra_directed, with_thread_pool, Distance, PathDirection};
+use crate::utility::edge_cost::{EdgeCost, EdgeWeights};
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
+
+//compute_density(), node_degree, compute_degree_distribution, compute_strength, compute_strength_distribution,
+//GraphSummary, summarize_graph, summarize_graphs, display_graph_summaries, analyze_graphs,
+//compute_degree_centrality, compute_closeness, compute_centrality, normalize_degree, compute_statistics, find_top_nodes, compute_betweenness_centrality,
+//normalize_betweenness, CentralityReport, compute_centrality_report, centrality_reports, display_centrality_reports,
+//analyze_centrality_across_periods
+
+/************************************************
+*
+*   Compute the Density of given graph, counting either distinct
+*   neighbours or individual transfers (which may exceed 1.0)
+*
+*************************************************/
+
+pub fn compute_density(graph: &TransactionGraph, counting: EdgeCounting) -> f64 {
+    let num_nodes = graph.node_count();
+    let num_edges: usize = match counting {
+        EdgeCounting::Distinct => graph.edge_count(),
+        EdgeCounting::Transfers => graph.transfer_count(),
+    };
+  
+    if num_nodes < 2 {
+        return 0.0; // A single-node or empty graph has no density
+    }
+  
+    num_edges as f64 / (num_nodes as f64 * (num_nodes as f64 - 1.0))
+}
+
+/**************************************************************
+*
+*   The direction in which the edges of a node are counted:
+*   In: transfers received, Out: transfers sent, Total: both
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DegreeDirection {
+    In,
+    Out,
+    Total,
+}
+
+/**************************************************************
+*
+*   Helper function to count the edges of a node in the given
+*   direction, distinct neighbours or individual transfers
+*
+***************************************************************/
+
+pub fn node_degree(graph: &TransactionGraph, node: NodeId, direction: DegreeDirection, counting: EdgeCounting) -> usize {
+    let out_degree = || match counting {
+        EdgeCounting::Distinct => graph.out_degree(node),
+        EdgeCounting::Transfers => graph.out_edges(node).map(|(_, edge)| edge.count).sum(),
+    };
+    let in_degree = || match counting {
+        EdgeCounting::Distinct => graph.in_degree(node),
+        EdgeCounting::Transfers => graph.in_edges(node).map(|(_, edge)| edge.count).sum(),
+    };
+    match direction {
+        DegreeDirection::In => in_degree(),
+        DegreeDirection::Out => out_degree(),
+        DegreeDirection::Total => in_degree() + out_degree(),
+    }
+}
+
+/**************************************************************
+*
+*   Compute the Degree Distribution of given graph in the given
+*   direction, the degree of a node is its number of distinct
+*   neighbours or of transfers. The result is a histogram of
+*   degree -> number of nodes, sorted by degree
+*
+***************************************************************/
+
+pub fn compute_degree_distribution(
+    graph: &TransactionGraph,
+    direction: DegreeDirection,
+    counting: EdgeCounting,
+) -> BTreeMap<usize, usize> {
+    let mut degree_count: BTreeMap<usize, usize> = BTreeMap::new();
+  
+    for node in graph.nodes() {
+        let degree = node_degree(graph, node, direction, counting);
+        *degree_count.entry(degree).or_default() += 1;
+    }
+  
+    degree_count
+}
+
+/**************************************************************
+*
+*   Compute the value-weighted strength of every node, the summed
+*   value of the transfers it received, sent or both, indexed by
+*   NodeId
+*
+***************************************************************/
+
+pub fn compute_strength(graph: &TransactionGraph, direction: DegreeDirection) -> Vec<f64> {
+    graph
+        .nodes()
+        .map(|node| {
+            let received: f64 = graph.in_edges(node).map(|(_, edge)| edge.total_value).sum();
+            let sent: f64 = graph.out_edges(node).map(|(_, edge)| edge.total_value).sum();
+            match direction {
+                DegreeDirection::In => received,
+                DegreeDirection::Out => sent,
+                DegreeDirection::Total => received + sent,
+            }
+        })
+        .collect()
+}
+
+/**************************************************************
+*
+*   The bins of the strength histogram, values span many orders
+*   of magnitude so each bin is one decade [10^k, 10^(k+1)),
+*   nodes that moved no value are counted in the Zero bin
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StrengthBin {
+    Zero,
+    Decade(i32),
+}
+
+impl std::fmt::Display for StrengthBin {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            StrengthBin::Zero => write!(f, "0"),
+            StrengthBin::Decade(k) => write!(f, "[1e{}, 1e{})", k, k + 1),
+        }
+    }
+}
+
+// Serialized as its label so the bins can be the keys of a JSON object
+impl Serialize for StrengthBin {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/**************************************************************
+*
+*   Compute the histogram of the given strengths, sorted by bin
+*
+***************************************************************/
+
+pub fn compute_strength_distribution(strength: &[f64]) -> BTreeMap<StrengthBin, usize> {
+    let mut bin_count: BTreeMap<StrengthBin, usize> = BTreeMap::new();
+    for &value in strength {
+        let bin = if value > 0.0 {
+            StrengthBin::Decade(value.log10().floor() as i32)
+        } else {
+            StrengthBin::Zero
+        };
+        *bin_count.entry(bin).or_default() += 1;
+    }
+    bin_count
+}
+
+fn print_distribution_table<K: Ord + Copy + std::fmt::Display>(
+    title: &str,
+    periods: &[&str],
+    histograms: &[&BTreeMap<K, usize>],
+) {
+    println!("{}", title);
+    print!("{:>20}", "bin");
+    for period in periods {
+        print!(" {:>12}", period);
+    }
+    println!();
+
+    let bins: BTreeSet<K> = histograms.iter().flat_map(|histogram| histogram.keys().copied()).collect();
+    for bin in bins {
+        print!("{:>20}", bin.to_string());
+        for histogram in histograms {
+            print!(" {:>12}", histogram.get(&bin).copied().unwrap_or(0));
+        }
+        println!();
+    }
+    println!();
+}
+
+/**************************************************************
+*
+*   The structural summary of the graph of one period:
+*   nodes: the address counts by role
+*   edges, transfers, density: the size of the graph
+*   in_degree, out_degree, total_degree: degree -> number of nodes
+*   in_strength, out_strength: strength bin -> number of nodes
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphSummary {
+    pub period: String,
+    pub nodes: NodeCounts,
+    pub edges: usize,
+    pub transfers: usize,
+    pub density: f64,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub in_degree: BTreeMap<usize, usize>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub out_degree: BTreeMap<usize, usize>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub total_degree: BTreeMap<usize, usize>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub in_strength: BTreeMap<StrengthBin, usize>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub out_strength: BTreeMap<StrengthBin, usize>,
+}
+
+/**************************************************************
+*
+*   Summarize one graph, the distributions are left empty unless
+*   asked for
+*
+***************************************************************/
+
+pub fn summarize_graph(period: &str, graph: &TransactionGraph, counting: EdgeCounting, distributions: bool) -> GraphSummary {
+    let mut summary = GraphSummary {
+        period: period.to_string(),
+        nodes: graph.node_counts(),
+        edges: graph.edge_count(),
+        transfers: graph.transfer_count(),
+        density: compute_density(graph, counting),
+        in_degree: BTreeMap::new(),
+        out_degree: BTreeMap::new(),
+        total_degree: BTreeMap::new(),
+        in_strength: BTreeMap::new(),
+        out_strength: BTreeMap::new(),
+    };
+    if distributions {
+        summary.in_degree = compute_degree_distribution(graph, DegreeDirection::In, counting);
+        summary.out_degree = compute_degree_distribution(graph, DegreeDirection::Out, counting);
+        summary.total_degree = compute_degree_distribution(graph, DegreeDirection::Total, counting);
+        summary.in_strength = compute_strength_distribution(&compute_strength(graph, DegreeDirection::In));
+        summary.out_strength = compute_strength_distribution(&compute_strength(graph, DegreeDirection::Out));
+    }
+    summary
+}
+
+/**************************************************************
+*
+*   Summarize the graph of every defined period, e.g. Before,
+*   During, After LUNA crashes, all of them over the complete
+*   address set
+*
+***************************************************************/
+
+pub fn summarize_graphs(graphs: &PeriodGraphs, counting: EdgeCounting, distributions: bool) -> Vec<GraphSummary> {
+    graphs.iter().map(|(period, graph)| summarize_graph(period, graph, counting, distributions)).collect()
+}
+
+/**************************************************************
+*
+*   Print the summaries on the console, the distributions as one
+*   table per metric with a column for each period when they were
+*   computed
+*
+***************************************************************/
+
+pub fn display_graph_summaries(summaries: &[GraphSummary]) {
+    for summary in summaries {
+        let counts = &summary.nodes;
+        println!(
+            "Addresses [{}]: {} total, {} senders, {} receivers, {} receive-only, {} send-only",
+            summary.period, counts.total, counts.senders, counts.receivers, counts.receive_only, counts.send_only
+        );
+    }
+
+    println!();
+    for summary in summaries {
+        println!("Graph Density [{}]: {:?}", summary.period, summary.density);
+    }
+
+    println!();
+    if summaries.iter().all(|summary| summary.total_degree.is_empty()) {
+        return;
+    }
+    let periods: Vec<&str> = summaries.iter().map(|summary| summary.period.as_str()).collect();
+    let histograms = |histogram: fn(&GraphSummary) -> &BTreeMap<usize, usize>| -> Vec<&BTreeMap<usize, usize>> {
+        summaries.iter().map(histogram).collect()
+    };
+    print_distribution_table("In-Degree Distribution:", &periods, &histograms(|summary| &summary.in_degree));
+    print_distribution_table("Out-Degree Distribution:", &periods, &histograms(|summary| &summary.out_degree));
+    print_distribution_table("Total-Degree Distribution:", &periods, &histograms(|summary| &summary.total_degree));
+    let in_strength: Vec<_> = summaries.iter().map(|summary| &summary.in_strength).collect();
+    print_distribution_table("In-Strength Distribution (value):", &periods, &in_strength);
+    let out_strength: Vec<_> = summaries.iter().map(|summary| &summary.out_strength).collect();
+    print_distribution_table("Out-Strength Distribution (value):", &periods, &out_strength);
+}
+
+/**************************************************************
+*
+*   Wrapper Function Call From main.rs
+*   compute the address counts, density, degree and strength
+*   distributions of the graph of every period and print them
+*
+***************************************************************/
+
+pub fn analyze_graphs(graphs: &PeriodGraphs, counting: EdgeCounting) {
+    display_graph_summaries(&summarize_graphs(graphs, counting, true));
+}
+
+/**************************************************************
+*
+*   The definitions of closeness centrality, with n nodes and for
+*   a node u that reaches r other nodes at a summed distance S:
+*   Classic: (n - 1) / S, only over the reachable nodes, which
+*   inflates nodes that reach a single neighbour cheaply
+*   Harmonic: sum of 1 / d(u, v) over the other nodes, divided by
+*   n - 1, unreachable nodes add 0 and the source itself is left
+*   out. Zero-value transfers are not paths with the value cost,
+*   other zero costs are raised to the min_cost of EdgeWeights
+*   WassermanFaust: (r / (n - 1)) * (r / S), the classic closeness
+*   within the reachable set scaled by the fraction reached
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClosenessVariant {
+    Classic,
+    #[default]
+    Harmonic,
+    WassermanFaust,
+}
+
+impl std::str::FromStr for ClosenessVariant {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "classic" => Ok(ClosenessVariant::Classic),
+            "harmonic" => Ok(ClosenessVariant::Harmonic),
+            "wasserman-faust" => Ok(ClosenessVariant::WassermanFaust),
+            _ => Err(format!("unknown closeness '{}', expected classic, harmonic or wasserman-faust", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Compute the out-degree centrality of given graph, indexed by
+*   NodeId
+*
+***************************************************************/
+
+pub fn compute_degree_centrality(graph: &TransactionGraph, counting: EdgeCounting) -> Vec<usize> {
+    graph
+        .nodes()
+        .map(|node| node_degree(graph, node, DegreeDirection::Out, counting))
+        .collect()
+}
+
+/**************************************************************
+*
+*   Compute the closeness centrality of given graph with the
+*   chosen definition, out-closeness uses the distances from a
+*   node and in-closeness the distances to it. One dijkstra per
+*   source node with the given edge costs is spread over the
+*   current rayon thread pool,
+*   collect keeps the NodeId order so the result does not depend
+*   on the scheduling
+*
+***************************************************************/
+
+pub fn compute_closeness(
+    graph: &TransactionGraph,
+    weights: &EdgeWeights,
+    variant: ClosenessVariant,
+    direction: PathDirection,
+) -> Vec<f64> {
+    let n = graph.node_count();
+    (0..n as NodeId)
+        .into_par_iter()
+        .map(|node| {
+            let shortest_paths = dijkstra_directed(graph, node, weights, direction);
+            let others = shortest_paths
+                .iter()
+                .enumerate()
+                .filter(|&(other, distance)| other != node as usize && distance.is_finite())
+                .map(|(_, &distance)| distance);
+            match variant {
+                ClosenessVariant::Classic | ClosenessVariant::WassermanFaust => {
+                    let (reached, total_distance) = others.fold((0usize, 0.0), |(count, sum), distance| (count + 1, sum + distance));
+                    if total_distance <= 0.0 {
+                        0.0
+                    } else if variant == ClosenessVariant::Classic {
+                        (n - 1) as f64 / total_distance
+                    } else {
+                        (reached as f64 / (n - 1) as f64) * (reached as f64 / total_distance)
+                    }
+                }
+                ClosenessVariant::Harmonic => {
+                    if n < 2 {
+                        return 0.0;
+                    }
+                    others.map(|distance| 1.0 / distance).sum::<f64>() / (n - 1) as f64
+                }
+            }
+        })
+        .collect()
+}
+
+/**************************************************************
+*
+*   Compute the degree centrality and closeness centrality of 
+*   given graph, both indexed by NodeId, the closeness is the
+*   classic out-closeness with the transferred value as distance
+*
+***************************************************************/
+
+pub fn compute_centrality(
+    graph: &TransactionGraph,
+    counting: EdgeCounting,
+  ) -> (Vec<usize>, Vec<f64>) {
+    // Degree Centrality
+    let degree_centrality = compute_degree_centrality(graph, counting);
+
+    // Closeness Centrality
+    let weights = EdgeWeights::new(graph, EdgeCost::Value, counting);
+    let closeness_centrality = compute_closeness(graph, &weights, ClosenessVariant::Classic, PathDirection::Outgoing);
+
+    (degree_centrality, closeness_centrality)
+}
+
+/**************************************************************
+*
+*   Helper function to normalize the centrality metrics, which
+*   normalizes the degree centrality, max_possible_degree is the
+*   number of other addresses (node_count - 1)
+*
+***************************************************************/
+
+pub fn normalize_degree(centrality: &[usize], max_possible_degree: usize) -> Vec<f64> {
+    if max_possible_degree == 0 {
+        return vec![0.0; centrality.len()]; // A single-node graph has no possible edge
+    }
+    centrality.iter()
+        .map(|&degree| degree as f64 / max_possible_degree as f64)
+        .collect()
+}
+
+/**************************************************************
+*
+*   Helper function to calculate statistics of centrality metrics,
+*   the mean and variance of given centrality metric
+*
+***************************************************************/
+
+pub fn compute_statistics(centrality: &[f64]) -> (f64, f64) {
+    if centrality.is_empty() {
+        return (0.0, 0.0);
+    }
+    let n = centrality.len() as f64;
+    let mean = centrality.iter().sum::<f64>() / n;
+    let variance = centrality.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    (mean, variance)
+}
+
+/**************************************************************
+*
+*   Helper function to find Top Degree Nodes (Most Connected Addresses)
+*   from given degree centrality metric(normalized), ties are broken
+*   by NodeId so the ranking is the same every run, NaN values
+*   rank last
+*
+***************************************************************/
+
+pub fn find_top_nodes(graph: &TransactionGraph, centrality: &[f64], top_n: usize) -> Vec<(String, f64)> {
+    let mut centrality_vec: Vec<(usize, f64)> = centrality.iter().copied().enumerate().collect();
+    let score = |value: f64| if value.is_nan() { f64::NEG_INFINITY } else { value };
+    centrality_vec.sort_by(|a, b| score(b.1).total_cmp(&score(a.1)).then(a.0.cmp(&b.0)));
+    centrality_vec
+        .into_iter()
+        .take(top_n)
+        .map(|(node, value)| (graph.address(node as NodeId).to_string(), value))
+        .collect()
+}
+
+/**************************************************************
+*
+*   The weighting of the shortest paths counted by betweenness:
+*   Unweighted: every edge is one hop (breadth first search)
+*   Weighted: the edges weigh their cost like in dijkstra
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BetweennessWeighting {
+    Unweighted,
+    #[default]
+    Weighted,
+}
+
+impl std::str::FromStr for BetweennessWeighting {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "weighted" => Ok(BetweennessWeighting::Weighted),
+            "unweighted" => Ok(BetweennessWeighting::Unweighted),
+            _ => Err(format!("unknown betweenness '{}', expected weighted or unweighted", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Approximate betweenness by only accumulating the dependencies
+*   of a uniform random sample of source nodes, the sample is
+*   drawn from the seed so the result is reproducible
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceSample {
+    pub sources: usize,
+    pub seed: u64,
+}
+
+// Number of betweenness sources accumulated by one parallel task
+const BETWEENNESS_CHUNK: usize = 64;
+
+/**************************************************************
+*
+*   Helper function for Brandes' algorithm, single source shortest
+*   paths that returns the nodes in order of non-decreasing distance,
+*   the number of shortest paths sigma to every node and the
+*   predecessors of every node on those paths
+*
+***************************************************************/
+
+fn shortest_path_dag(
+    graph: &TransactionGraph,
+    source: NodeId,
+    weighting: BetweennessWeighting,
+    weights: &EdgeWeights,
+) -> (Vec<NodeId>, Vec<f64>, Vec<Vec<NodeId>>) {
+    let n = graph.node_count();
+    let mut order: Vec<NodeId> = Vec::new();
+    let mut sigma: Vec<f64> = vec![0.0; n];
+    let mut predecessors: Vec<Vec<NodeId>> = vec![Vec::new(); n];
+    let mut distances: Vec<f64> = vec![f64::INFINITY; n];
+
+    sigma[source as usize] = 1.0;
+    distances[source as usize] = 0.0;
+
+    match weighting {
+        BetweennessWeighting::Unweighted => {
+            let mut queue = VecDeque::from([source]);
+            while let Some(current) = queue.pop_front() {
+                order.push(current);
+                for (neighbor, _) in graph.out_edges(current) {
+                    let new_dist = distances[current as usize] + 1.0;
+                    if distances[neighbor as usize].is_infinite() {
+                        distances[neighbor as usize] = new_dist;
+                        queue.push_back(neighbor);
+                    }
+                    if distances[neighbor as usize] == new_dist {
+                        sigma[neighbor as usize] += sigma[current as usize];
+                        predecessors[neighbor as usize].push(current);
+                    }
+                }
+            }
+        }
+        BetweennessWeighting::Weighted => {
+            let mut visited: Vec<bool> = vec![false; n];
+            let mut heap = BinaryHeap::new();
+            heap.push(Reverse((Distance(0.0), source)));
+            while let Some(Reverse((dist, current))) = heap.pop() {
+                if visited[current as usize] {
+                    continue;
+                }
+                visited[current as usize] = true;
+                order.push(current);
+                for edge in graph.out_edge_ids(current) {
+                    let neighbor = graph.edge_target(edge);
+                    let new_dist = dist.0 + weights.weight(edge);
+                    if new_dist < distances[neighbor as usize] {
+                        distances[neighbor as usize] = new_dist;
+                        sigma[neighbor as usize] = sigma[current as usize];
+                        predecessors[neighbor as usize] = vec![current];
+                        heap.push(Reverse((Distance(new_dist), neighbor)));
+                    } else if new_dist == distances[neighbor as usize] && !visited[neighbor as usize] {
+                        sigma[neighbor as usize] += sigma[current as usize];
+                        predecessors[neighbor as usize].push(current);
+                    }
+                }
+            }
+        }
+    }
+
+    (order, sigma, predecessors)
+}
+
+/**************************************************************
+*
+*   Compute the betweenness centrality of a graph, which is a
+*   measure of centrality in a graph based on shortest paths,
+*   with Brandes' dependency accumulation instead of listing
+*   every shortest path: O(n * m) for unweighted and
+*   O(n * m * log n) for weighted paths. With a source sample
+*   the accumulated dependencies are scaled by n / sources to
+*   estimate the exact value. The result is indexed by NodeId
+*   and not normalized. The sources are processed on the current
+*   rayon thread pool
+*
+***************************************************************/
+
+pub fn compute_betweenness_centrality(
+    graph: &TransactionGraph,
+    weighting: BetweennessWeighting,
+    weights: &EdgeWeights,
+    sample: Option<SourceSample>,
+) -> Vec<f64> {
+    let n = graph.node_count();
+    let mut betweenness_centrality: Vec<f64> = vec![0.0; n];
+
+    let (sources, scale): (Vec<NodeId>, f64) = match sample {
+        Some(sample) if sample.sources < n => {
+            let mut rng = StdRng::seed_from_u64(sample.seed);
+            let mut sources: Vec<NodeId> = index::sample(&mut rng, n, sample.sources)
+                .into_iter()
+                .map(|node| node as NodeId)
+                .collect();
+            sources.sort();
+            let scale = n as f64 / sample.sources.max(1) as f64;
+            (sources, scale)
+        }
+        _ => (graph.nodes().collect(), 1.0),
+    };
+
+    // The sources are split into fixed chunks accumulated in parallel, the partial sums are
+    // then added in chunk order so the floating point result is the same for any thread count
+    let partial_sums: Vec<Vec<f64>> = sources
+        .par_chunks(BETWEENNESS_CHUNK)
+        .map(|chunk| {
+            let mut partial: Vec<f64> = vec![0.0; n];
+            let mut delta: Vec<f64> = vec![0.0; n];
+            for &source in chunk {
+                let (order, sigma, predecessors) = shortest_path_dag(graph, source, weighting, weights);
+
+                // Accumulate the dependencies from the farthest node back to the source
+                for &node in &order {
+                    delta[node as usize] = 0.0;
+                }
+                for &node in order.iter().rev() {
+                    for &predecessor in &predecessors[node as usize] {
+                        delta[predecessor as usize] +=
+                            sigma[predecessor as usize] / sigma[node as usize] * (1.0 + delta[node as usize]);
+                    }
+                    if node != source {
+                        partial[node as usize] += delta[node as usize];
+                    }
+                }
+            }
+            partial
+        })
+        .collect();
+
+    for partial in partial_sums {
+        for (total, value) in betweenness_centrality.iter_mut().zip(partial) {
+            *total += value * scale;
+        }
+    }
+
+    betweenness_centrality
+}
+
+/**************************************************************
+*
+*   Normalizes the result after computed the betweeness centrality,
+*   divided by the (n - 1)(n - 2) ordered pairs a node could lie
+*   between in a directed graph
+*
+***************************************************************/
+
+pub fn normalize_betweenness(centrality: &[f64], n: usize) -> Vec<f64> {
+    if n < 3 {
+        return vec![0.0; centrality.len()]; // No node can lie between two others
+    }
+    let max_possible = (n - 1) as f64 * (n - 2) as f64;
+    centrality.iter()
+        .map(|&value| value / max_possible)
+        .collect()
+}
+
+/**************************************************************
+*
+*   The options of the centrality analysis of every period:
+*   counting: how parallel transfers are counted
+*   cost: the edge cost preset of the closeness and weighted
+*   betweenness shortest paths
+*   closeness, closeness_direction: the closeness definition and
+*   whether out- or in-closeness is reported
+*   betweenness: the weighting of the betweenness shortest paths
+*   sample: optional source sample for approximate betweenness
+*   threads: number of worker threads, all cores when None
+*   top_nodes: the number of top addresses listed per measure
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CentralityOptions {
+    pub counting: EdgeCounting,
+    pub cost: EdgeCost,
+    pub closeness: ClosenessVariant,
+    pub closeness_direction: PathDirection,
+    pub betweenness: BetweennessWeighting,
+    pub sample: Option<SourceSample>,
+    pub threads: Option<usize>,
+    pub top_nodes: usize,
+}
+
+impl Default for CentralityOptions {
+    fn default() -> Self {
+        CentralityOptions {
+            counting: EdgeCounting::default(),
+            cost: EdgeCost::default(),
+            closeness: ClosenessVariant::default(),
+            closeness_direction: PathDirection::default(),
+            betweenness: BetweennessWeighting::default(),
+            sample: None,
+            threads: None,
+            top_nodes: TOP_NODES,
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The default number of top addresses listed for every centrality
+*
+***************************************************************/
+
+pub const TOP_NODES: usize = 5;
+
+/**************************************************************
+*
+*   One address with its centrality value
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RankedNode {
+    pub address: String,
+    pub value: f64,
+}
+
+/**************************************************************
+*
+*   The summary statistics of one normalised centrality measure
+*   over the addresses of a graph, with its top addresses
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CentralitySummary {
+    pub mean: f64,
+    pub variance: f64,
+    pub top: Vec<RankedNode>,
+}
+
+impl CentralitySummary {
+    fn new(graph: &TransactionGraph, centrality: &[f64], top_n: usize) -> Self {
+        let (mean, variance) = compute_statistics(centrality);
+        let top = find_top_nodes(graph, centrality, top_n)
+            .into_iter()
+            .map(|(address, value)| RankedNode { address, value })
+            .collect();
+        CentralitySummary { mean, variance, top }
+    }
+}
+
+/**************************************************************
+*
+*   The normalised degree, closeness and betweenness of one address
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NodeCentrality {
+    pub address: String,
+    pub degree: f64,
+    pub closeness: f64,
+    pub betweenness: f64,
+}
+
+/**************************************************************
+*
+*   The centrality analysis of the graph of one period:
+*   closeness_definition: the closeness variant and direction used
+*   degree, closeness, betweenness: the summary of every measure
+*   nodes: the centralities of every address, indexed by NodeId
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CentralityReport {
+    pub period: String,
+    pub closeness_definition: String,
+    pub degree: CentralitySummary,
+    pub closeness: CentralitySummary,
+    pub betweenness: CentralitySummary,
+    pub nodes: Vec<NodeCentrality>,
+}
+
+pub fn compute_centrality_report(period: &str, graph: &TransactionGraph, options: &CentralityOptions) -> CentralityReport {
+    // Compute centralities
+    let weights = EdgeWeights::new(graph, options.cost, options.counting);
+    let (degree, closeness, betweenness) = with_thread_pool(options.threads, || {
+        let degree = compute_degree_centrality(graph, options.counting);
+        let closeness = compute_closeness(graph, &weights, options.closeness, options.closeness_direction);
+        let betweenness = compute_betweenness_centrality(graph, options.betweenness, &weights, options.sample);
+        (degree, closeness, betweenness)
+    });
+
+    // Normalize centrality metrics
+    let norm_degree = normalize_degree(&degree, graph.node_count().saturating_sub(1));
+    let norm_betweenness = normalize_betweenness(&betweenness, graph.node_count());
+
+    CentralityReport {
+        period: period.to_string(),
+        closeness_definition: format!("{:?}, {:?}", options.closeness, options.closeness_direction),
+        degree: CentralitySummary::new(graph, &norm_degree, options.top_nodes),
+        closeness: CentralitySummary::new(graph, &closeness, options.top_nodes),
+        betweenness: CentralitySummary::new(graph, &norm_betweenness, options.top_nodes),
+        nodes: graph
+            .nodes()
+            .map(|node| NodeCentrality {
+                address: graph.address(node).to_string(),
+                degree: norm_degree[node as usize],
+                closeness: closeness[node as usize],
+                betweenness: norm_betweenness[node as usize],
+            })
+            .collect(),
+    }
+}
+
+pub fn centrality_reports(graphs: &PeriodGraphs, options: &CentralityOptions) -> Vec<CentralityReport> {
+    graphs.iter().map(|(period, graph)| compute_centrality_report(period, graph, options)).collect()
+}
+
+/**************************************************************
+*
+*   Print the centrality statistics and top addresses of every
+*   period on the console
+*
+***************************************************************/
+
+pub fn display_centrality_reports(reports: &[CentralityReport]) {
+    let top = |summary: &CentralitySummary| -> Vec<(String, f64)> {
+        summary.top.iter().map(|node| (node.address.clone(), node.value)).collect()
+    };
+    println!("=== Centrality Analysis ===");
+    for report in reports {
+        let period = &report.period;
+        println!("Degree Centrality Mean [{}]: {:?}", period, report.degree.mean);
+        println!("Degree Centrality Variance [{}]: {:?}", period, report.degree.variance);
+        println!("Closeness Centrality Mean [{}]: {:?}", period, report.closeness.mean);
+        println!("Closeness Centrality Variance [{}]: {:?}", period, report.closeness.variance);
+        println!("Betweenness Centrality Mean [{}]: {:?}", period, report.betweenness.mean);
+        println!("Betweenness Centrality Variance [{}]: {:?}", period, report.betweenness.variance);
+
+        println!("Top Degree Nodes [{}]: {:?}", period, top(&report.degree));
+        println!("Top Closeness Nodes ({}) [{}]: {:?}", report.closeness_definition, period, top(&report.closeness));
+        println!("Top Betweenness Nodes [{}]: {:?}\n", period, top(&report.betweenness));
+    }
+}
+
+/**************************************************************
+*
+*   The wrapper function that encompass all the analysis part for convenience
+*   to be called by main.rs
+*
+***************************************************************/
+
+pub fn analyze_centrality_across_periods(graphs: &PeriodGraphs, options: &CentralityOptions) {
+    display_centrality_reports(&centrality_reports(graphs, options));
+}
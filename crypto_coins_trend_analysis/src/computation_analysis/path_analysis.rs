@@ -0,0 +1,130 @@
+use serde::Serialize;
+use crate::data_cleaning_load::data_loader::PeriodGraphs;
+use crate::graph::transaction_graph::{TransactionGraph, NodeId, EdgeCounting};
+use crate::utility::edge_cost::{EdgeCost, EdgeWeights};
+use crate::utility::helper_algorithm::{shortest_path_tree, trace_path, PathDirection};
+
+//PathReport, find_address, find_paths, path_reports, display_path_reports
+
+/**************************************************************
+*
+*   One shortest path of the graph of a period:
+*   from, to: the end addresses
+*   cost: the summed edge cost of the path
+*   path: the addresses from the sender to the receiver
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathReport {
+    pub period: String,
+    pub from: String,
+    pub to: String,
+    pub cost: f64,
+    pub path: Vec<String>,
+}
+
+/**************************************************************
+*
+*   Find the node of an address, exports differ in the case of the
+*   hex digits so the address is also matched ignoring case
+*
+***************************************************************/
+
+pub fn find_address(graph: &TransactionGraph, address: &str) -> Option<NodeId> {
+    graph
+        .node_id(address)
+        .or_else(|| graph.nodes().find(|&node| graph.address(node).eq_ignore_ascii_case(address)))
+}
+
+/**************************************************************
+*
+*   The shortest paths from an address in the graph of one period,
+*   to the given address, or to the limit nearest addresses it
+*   reaches when no target is given (ties by NodeId). A period in
+*   which an address does not occur has no paths
+*
+***************************************************************/
+
+pub fn find_paths(
+    period: &str,
+    graph: &TransactionGraph,
+    from: &str,
+    to: Option<&str>,
+    cost: EdgeCost,
+    counting: EdgeCounting,
+    limit: usize,
+) -> Vec<PathReport> {
+    let Some(start) = find_address(graph, from) else {
+        return Vec::new();
+    };
+    let targets: Vec<NodeId> = match to {
+        Some(to) => find_address(graph, to).into_iter().collect(),
+        None => Vec::new(),
+    };
+    if to.is_some() && targets.is_empty() {
+        return Vec::new();
+    }
+
+    let weights = EdgeWeights::new(graph, cost, counting);
+    let (distances, predecessors) = shortest_path_tree(graph, start, &weights, PathDirection::Outgoing);
+    let targets = if to.is_some() {
+        targets
+    } else {
+        let mut reached: Vec<NodeId> = graph
+            .nodes()
+            .filter(|&node| node != start && distances[node as usize].is_finite())
+            .collect();
+        reached.sort_by(|a, b| distances[*a as usize].total_cmp(&distances[*b as usize]).then(a.cmp(b)));
+        reached.truncate(limit);
+        reached
+    };
+
+    targets
+        .into_iter()
+        .filter(|&target| distances[target as usize].is_finite())
+        .map(|target| PathReport {
+            period: period.to_string(),
+            from: graph.address(start).to_string(),
+            to: graph.address(target).to_string(),
+            cost: distances[target as usize],
+            path: trace_path(&predecessors, target).into_iter().map(|node| graph.address(node).to_string()).collect(),
+        })
+        .collect()
+}
+
+pub fn path_reports(
+    graphs: &PeriodGraphs,
+    from: &str,
+    to: Option<&str>,
+    cost: EdgeCost,
+    counting: EdgeCounting,
+    limit: usize,
+) -> Vec<PathReport> {
+    graphs
+        .iter()
+        .flat_map(|(period, graph)| find_paths(period, graph, from, to, cost, counting, limit))
+        .collect()
+}
+
+/**************************************************************
+*
+*   Print the paths on the console, one line per path
+*
+***************************************************************/
+
+pub fn display_path_reports(reports: &[PathReport]) {
+    println!("=== Shortest Paths ===");
+    for report in reports {
+        println!(
+            "[{}] {} -> {}: cost {}, {} hops: {}",
+            report.period,
+            report.from,
+            report.to,
+            report.cost,
+            report.path.len() - 1,
+            report.path.join(" -> ")
+        );
+    }
+    println!();
+}
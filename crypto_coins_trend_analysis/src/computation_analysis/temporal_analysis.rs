@@ -0,0 +1,223 @@
+use crate::data_cleaning_load::data_loader::{PeriodGraphs, LoadOptions, stream_transfers};
+use crate::data_cleaning_load::validation::LoadSummary;
+use crate::data_cleaning_load::period_config::{Period, PeriodSpec};
+use crate::data_cleaning_load::column_mapping::format_timestamp;
+use crate::graph::transaction_graph::{TransactionGraph, NodeId, EdgeCounting};
+use crate::graph::incremental_graph::IncrementalGraph;
+use crate::computation_analysis::computation_algorithms::{compute_density, node_degree, DegreeDirection};
+use crate::connected_component::find_connected_component::{weakly_connected_components, largest_components_over_slots};
+use std::collections::HashMap;
+use rayon::prelude::*;
+use serde::Serialize;
+
+//TOP_CONCENTRATION_NODES, WindowMetrics, compute_window_metrics, compute_time_series,
+//compute_time_series_incremental, load_time_series, display_time_series, analyze_time_series
+
+/**************************************************************
+*
+*   The number of highest-degree addresses whose share of all
+*   connections measures the top-node concentration of a window
+*
+***************************************************************/
+
+pub const TOP_CONCENTRATION_NODES: usize = 10;
+
+/**************************************************************
+*
+*   The metrics of the graph of one time window:
+*   start, end: the window start <= t < end
+*   edges, transfers: distinct edges and individual transfers
+*   active_addresses: addresses that sent or received in the window
+*   density: the density of the window graph
+*   largest_component: size of the largest weakly connected component
+*   top_concentration: share of the total degree held by the
+*   TOP_CONCENTRATION_NODES highest-degree addresses, 1.0 when a few
+*   hubs take part in every transfer
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowMetrics {
+    pub start: u64,
+    pub end: u64,
+    pub edges: usize,
+    pub transfers: usize,
+    pub active_addresses: usize,
+    pub density: f64,
+    pub largest_component: usize,
+    pub top_concentration: f64,
+}
+
+pub fn compute_window_metrics(graph: &TransactionGraph, window: &Period, counting: EdgeCounting) -> WindowMetrics {
+    let mut degrees: Vec<usize> = graph.nodes().map(|node| node_degree(graph, node, DegreeDirection::Total, counting)).collect();
+    degrees.sort_unstable_by(|a, b| b.cmp(a));
+    let total_degree: usize = degrees.iter().sum();
+    let top_degree: usize = degrees.iter().take(TOP_CONCENTRATION_NODES).sum();
+
+    WindowMetrics {
+        start: window.start,
+        end: window.end,
+        edges: graph.edge_count(),
+        transfers: graph.transfer_count(),
+        active_addresses: graph.node_count(),
+        density: compute_density(graph, counting),
+        largest_component: weakly_connected_components(graph).largest_size(),
+        top_concentration: if total_degree == 0 { 0.0 } else { top_degree as f64 / total_degree as f64 },
+    }
+}
+
+/**************************************************************
+*
+*   Compute the metrics of every window graph, the windows are
+*   independent so they are computed in parallel
+*
+***************************************************************/
+
+pub fn compute_time_series(graphs: &PeriodGraphs, windows: &PeriodSpec, counting: EdgeCounting) -> Vec<WindowMetrics> {
+    windows
+        .periods
+        .par_iter()
+        .filter_map(|window| graphs.get(&window.name).map(|graph| compute_window_metrics(graph, window, counting)))
+        .collect()
+}
+
+/**************************************************************
+*
+*   Compute the metrics of every window by sliding one incremental
+*   graph over the transfers instead of building a graph per window.
+*   The windows are visited by start time, the transfers before the
+*   window end are inserted and those before its start expired, so
+*   every transfer is added and removed once however much the
+*   windows overlap. The counts and degrees are read off the graph,
+*   the largest component of every window is found afterwards from
+*   the window range in which each edge stays alive. The windows
+*   must share one width as built by PeriodSpec::windows, the
+*   transfers are (source, target, timestamp) of the graph nodes
+*   in any order
+*
+***************************************************************/
+
+pub fn compute_time_series_incremental(
+    mut graph: IncrementalGraph,
+    mut transfers: Vec<(NodeId, NodeId, u64)>,
+    windows: &PeriodSpec,
+) -> Vec<WindowMetrics> {
+    transfers.sort_by_key(|&(_, _, timestamp)| timestamp);
+    let mut order: Vec<&Period> = windows.periods.iter().collect();
+    order.sort_by_key(|window| (window.start, window.end));
+
+    let mut series: Vec<WindowMetrics> = Vec::with_capacity(order.len());
+    // The window index from which every live edge has been alive, and the closed ranges
+    let mut alive_since: HashMap<(NodeId, NodeId), usize> = HashMap::new();
+    let mut alive: Vec<(NodeId, NodeId, usize, usize)> = Vec::new();
+    let mut next = 0;
+    for (index, window) in order.iter().enumerate() {
+        while next < transfers.len() && transfers[next].2 < window.end {
+            let (source, target, timestamp) = transfers[next];
+            if graph.insert(source, target, timestamp) {
+                alive_since.insert((source, target), index);
+            }
+            next += 1;
+        }
+        for pair in graph.expire_before(window.start) {
+            let since = alive_since.remove(&pair).expect("expired edge was never inserted");
+            alive.push((pair.0, pair.1, since, index));
+        }
+
+        series.push(WindowMetrics {
+            start: window.start,
+            end: window.end,
+            edges: graph.edge_count(),
+            transfers: graph.transfer_count(),
+            active_addresses: graph.node_count(),
+            density: graph.density(),
+            largest_component: 0,
+            top_concentration: graph.top_degree_share(TOP_CONCENTRATION_NODES),
+        });
+    }
+    alive.extend(alive_since.into_iter().map(|((source, target), since)| (source, target, since, order.len())));
+
+    let largest = largest_components_over_slots(graph.address_count(), &alive, order.len());
+    for (metrics, largest) in series.iter_mut().zip(largest) {
+        if metrics.active_addresses > 0 {
+            metrics.largest_component = largest;
+        }
+    }
+    series
+}
+
+/**************************************************************
+*
+*   Stream the dataset once and compute the time series of the
+*   windows incrementally, only the (source, target, timestamp) of
+*   the transfers inside the windows are kept in memory, restricted
+*   to the selected tokens (by symbol or contract address) if any
+*
+***************************************************************/
+
+pub fn load_time_series(
+    path: &str,
+    windows: &PeriodSpec,
+    options: &LoadOptions,
+    selection: &[String],
+    counting: EdgeCounting,
+) -> Result<(Vec<WindowMetrics>, LoadSummary), Box<dyn std::error::Error>> {
+    let (start, end) = windows.range();
+    let mut graph = IncrementalGraph::new(counting);
+    let mut transfers: Vec<(NodeId, NodeId, u64)> = Vec::new();
+    let summary = stream_transfers(path, options, |_, from_address, to_address, transaction, _| {
+        if transaction.timestamp < start || transaction.timestamp >= end {
+            return Ok(());
+        }
+        let symbol = options.tokens.symbol(&transaction.unit);
+        let selected = selection.is_empty()
+            || selection.iter().any(|wanted| wanted.eq_ignore_ascii_case(symbol) || wanted.eq_ignore_ascii_case(&transaction.unit));
+        if selected {
+            transfers.push((graph.add_node(from_address), graph.add_node(to_address), transaction.timestamp));
+        }
+        Ok(())
+    })?;
+    if options.progress {
+        eprintln!("  kept {} transfers in {} windows", transfers.len(), windows.periods.len());
+    }
+    Ok((compute_time_series_incremental(graph, transfers, windows), summary))
+}
+
+/**************************************************************
+*
+*   Print a time series, one row per window
+*
+***************************************************************/
+
+pub fn display_time_series(series: &[WindowMetrics]) {
+    println!("=== Temporal Analysis ===");
+    println!(
+        "{:>20} {:>8} {:>10} {:>8} {:>10} {:>8} {:>8}",
+        "window start", "edges", "transfers", "active", "density", "largest", format!("top-{}", TOP_CONCENTRATION_NODES)
+    );
+    for metrics in series {
+        println!(
+            "{:>20} {:>8} {:>10} {:>8} {:>10.6} {:>8} {:>8.3}",
+            format_timestamp(metrics.start),
+            metrics.edges,
+            metrics.transfers,
+            metrics.active_addresses,
+            metrics.density,
+            metrics.largest_component,
+            metrics.top_concentration
+        );
+    }
+    println!();
+}
+
+/**************************************************************
+*
+*   A wrapper function for convenience to be called in main.rs
+*   which prints the time series of the window graphs, one row
+*   per window
+*
+***************************************************************/
+
+pub fn analyze_time_series(graphs: &PeriodGraphs, windows: &PeriodSpec, counting: EdgeCounting) {
+    display_time_series(&compute_time_series(graphs, windows, counting));
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use crate::data_cleaning_load::data_loader::PeriodGraphs;
+use crate::graph::transaction_graph::{TransactionGraph, TokenId, EdgeCounting};
+use crate::computation_analysis::computation_algorithms::{compute_density, node_degree, DegreeDirection};
+use crate::connected_component::find_connected_component::{strongly_connected_components, weakly_connected_components};
+
+//resolve_tokens, filter_by_tokens, split_by_token, TokenStats, compute_token_stats,
+//TokenReport, compare_tokens, display_token_reports, analyze_tokens_across_periods
+
+/**************************************************************
+*
+*   Find the tokens of a graph by symbol or contract address,
+*   ignoring case, an empty selection selects every token
+*
+***************************************************************/
+
+pub fn resolve_tokens(graph: &TransactionGraph, selection: &[String]) -> Vec<TokenId> {
+    graph
+        .tokens()
+        .filter(|&token| {
+            selection.is_empty()
+                || selection.iter().any(|wanted| {
+                    wanted.eq_ignore_ascii_case(graph.token_symbol(token)) || wanted.eq_ignore_ascii_case(graph.token(token))
+                })
+        })
+        .collect()
+}
+
+/**************************************************************
+*
+*   Restrict the graph of every period to the selected tokens, the
+*   periods keep their names
+*
+***************************************************************/
+
+pub fn filter_by_tokens(graphs: &PeriodGraphs, selection: &[String]) -> PeriodGraphs {
+    graphs
+        .iter()
+        .map(|(period, graph)| (period.clone(), graph.filter_tokens(&resolve_tokens(graph, selection))))
+        .collect()
+}
+
+/**************************************************************
+*
+*   Split the graph of every period into one subgraph per selected
+*   token, named "period/SYMBOL", so every analysis that takes the
+*   period graphs could be run per token. When contracts share a
+*   symbol in any period, each of them is named
+*   "period/SYMBOL@address" instead so none overwrites another
+*
+***************************************************************/
+
+pub fn split_by_token(graphs: &PeriodGraphs, selection: &[String]) -> PeriodGraphs {
+    let mut contracts: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for graph in graphs.values() {
+        for token in resolve_tokens(graph, selection) {
+            contracts.entry(graph.token_symbol(token)).or_default().insert(graph.token(token));
+        }
+    }
+
+    let mut split = PeriodGraphs::new();
+    for (period, graph) in graphs {
+        for token in resolve_tokens(graph, selection) {
+            let symbol = graph.token_symbol(token);
+            let name = if contracts[symbol].len() > 1 {
+                format!("{}/{}@{}", period, symbol, graph.token(token))
+            } else {
+                format!("{}/{}", period, symbol)
+            };
+            split.insert(name, graph.token_subgraph(token));
+        }
+    }
+    split
+}
+
+/**************************************************************
+*
+*   The summary of one token subgraph used by the comparison table:
+*   nodes, edges, transfers, volume: size of the subgraph
+*   density, mean_degree, max_degree: how connected the addresses are
+*   weak_components, largest_weak_share: the number of weakly connected
+*   components and the share of the addresses in the largest one
+*   largest_strong: size of the largest strongly connected component
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct TokenStats {
+    pub nodes: usize,
+    pub edges: usize,
+    pub transfers: usize,
+    pub volume: f64,
+    pub density: f64,
+    pub mean_degree: f64,
+    pub max_degree: usize,
+    pub weak_components: usize,
+    pub largest_weak_share: f64,
+    pub largest_strong: usize,
+}
+
+pub fn compute_token_stats(graph: &TransactionGraph, counting: EdgeCounting) -> TokenStats {
+    let nodes = graph.node_count();
+    if nodes == 0 {
+        return TokenStats::default();
+    }
+    let degrees: Vec<usize> = graph.nodes().map(|node| node_degree(graph, node, DegreeDirection::Total, counting)).collect();
+    let weak = weakly_connected_components(graph);
+    TokenStats {
+        nodes,
+        edges: graph.edge_count(),
+        transfers: graph.transfer_count(),
+        volume: (0..graph.edge_count() as u32).map(|edge| graph.edge_by_id(edge).total_value).sum(),
+        density: compute_density(graph, counting),
+        mean_degree: degrees.iter().sum::<usize>() as f64 / nodes as f64,
+        max_degree: degrees.iter().copied().max().unwrap_or(0),
+        weak_components: weak.count(),
+        largest_weak_share: weak.largest_size() as f64 / nodes as f64,
+        largest_strong: strongly_connected_components(graph).largest_size(),
+    }
+}
+
+/**************************************************************
+*
+*   The statistics of one token in one period, the token is its
+*   contract address so tokens sharing a symbol stay apart
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TokenReport {
+    pub token: String,
+    pub symbol: String,
+    pub period: String,
+    pub stats: TokenStats,
+}
+
+/**************************************************************
+*
+*   Compare the subgraph of every selected token across the
+*   periods, grouped by token in the order the tokens first appear,
+*   a period without the token has empty statistics
+*
+***************************************************************/
+
+pub fn compare_tokens(graphs: &PeriodGraphs, selection: &[String], counting: EdgeCounting) -> Vec<TokenReport> {
+    let mut tokens: Vec<(String, String)> = Vec::new();
+    for graph in graphs.values() {
+        for token in resolve_tokens(graph, selection) {
+            if !tokens.iter().any(|(address, _)| address == graph.token(token)) {
+                tokens.push((graph.token(token).to_string(), graph.token_symbol(token).to_string()));
+            }
+        }
+    }
+
+    let mut reports = Vec::new();
+    for (address, symbol) in tokens {
+        for (period, graph) in graphs {
+            let selected: Vec<TokenId> = graph.token_id(&address).into_iter().collect();
+            reports.push(TokenReport {
+                token: address.clone(),
+                symbol: symbol.clone(),
+                period: period.clone(),
+                stats: compute_token_stats(&graph.filter_tokens(&selected), counting),
+            });
+        }
+    }
+    reports
+}
+
+/**************************************************************
+*
+*   Print one table per token comparing its subgraph across the
+*   periods, e.g. how the UST network fragmented during the crash
+*   while USDC did not
+*
+***************************************************************/
+
+pub fn display_token_reports(reports: &[TokenReport]) {
+    println!("=== Per-Token Comparison ===");
+    for (index, report) in reports.iter().enumerate() {
+        if index == 0 || reports[index - 1].token != report.token {
+            if report.symbol == report.token {
+                println!("[{}]", report.symbol);
+            } else {
+                println!("[{}] {}", report.symbol, report.token);
+            }
+            println!(
+                "{:>12} {:>8} {:>8} {:>10} {:>16} {:>10} {:>8} {:>8} {:>8} {:>10} {:>8}",
+                "period", "nodes", "edges", "transfers", "volume", "density", "mean deg", "max deg", "WCCs", "largest %", "max SCC"
+            );
+        }
+        let stats = &report.stats;
+        println!(
+            "{:>12} {:>8} {:>8} {:>10} {:>16.2} {:>10.6} {:>8.3} {:>8} {:>8} {:>10.1} {:>8}",
+            report.period,
+            stats.nodes,
+            stats.edges,
+            stats.transfers,
+            stats.volume,
+            stats.density,
+            stats.mean_degree,
+            stats.max_degree,
+            stats.weak_components,
+            100.0 * stats.largest_weak_share,
+            stats.largest_strong
+        );
+        if reports.get(index + 1).is_none_or(|next| next.token != report.token) {
+            println!();
+        }
+    }
+}
+
+/**************************************************************
+*
+*   A wrapper function for convenience which prints the per-token
+*   comparison of the selected tokens
+*
+***************************************************************/
+
+pub fn analyze_tokens_across_periods(graphs: &PeriodGraphs, selection: &[String], counting: EdgeCounting) {
+    display_token_reports(&compare_tokens(graphs, selection, counting));
+}
@@ -1,86 +1,568 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use crate::data_cleaning_load::data_loader::{Transaction, load_csv_convert_graph, display_graph};
-
-/**************************************************************
-*
-*   Compute the larges connected component of given graph, the
-*   the result will be the size of largest connected component
-*
-***************************************************************/
-
-pub fn largest_connected_component(graph: &HashMap<String, HashMap<String, Transaction>>) -> usize {
-  let mut visited = HashSet::new();
-  let mut max_size = 0;
-
-  // Sort the keys to ensure consistent iteration order, otherwise it may generate different result every run
-  let mut nodes: Vec<_> = graph.keys().cloned().collect();
-  nodes.sort();
-
-  for node in nodes {
-      if !visited.contains(&node) {
-          let size = dfs_component_size(graph, &node, &mut visited);
-          max_size = max_size.max(size);
-      }
-  }
-
-  max_size
-}
-
-/**************************************************************
-*
-*   The helper function used for calculation of size of connected
-*   component, the algorithm modifed from the DFS algorithm, it
-*   used stack to store the visited component and pop until all
-*   component is visited
-*
-***************************************************************/
-
-pub fn dfs_component_size(
-  graph: &HashMap<String, HashMap<String, Transaction>>,
-  start: &str,
-  visited: &mut HashSet<String>,
-) -> usize {
-  let mut stack = vec![start.to_string()];
-  let mut size = 0;
-
-  while let Some(node) = stack.pop() {
-      if visited.insert(node.clone()) {
-          size += 1;
-          if let Some(neighbors) = graph.get(&node) {
-              for neighbor in neighbors.keys() {
-                  if !visited.contains(neighbor) {
-                      stack.push(neighbor.clone());
-                  }
-              }
-          }
-      }
-  }
-
-  size
-}
-
-/**************************************************************
-*
-*   A wrapper function for convenience to be called in main.rs
-*   which analyze and display the largest connected component 
-*   of the graph
-*
-***************************************************************/
-
-pub fn analyze_largest_components(
-  graph_before: &HashMap<String, HashMap<String, Transaction>>,
-  graph_during: &HashMap<String, HashMap<String, Transaction>>,
-  graph_after: &HashMap<String, HashMap<String, Transaction>>,
-) {
-  // Find the largest connected component for each graph
-  let largest_before = largest_connected_component(graph_before);
-  let largest_during = largest_connected_component(graph_during);
-  let largest_after = largest_connected_component(graph_after);
-
-  // Output the results
-  println!("Largest Connected Component:");
-  println!("Before Crash: {}", largest_before);
-  println!("During Crash: {}", largest_during);
-  println!("After Crash: {}", largest_after);
-}
\ No newline at end of file
+use std::collections::BTreeMap;
+use serde::Serialize;
+use crate::data_cleaning_load::data_loader::PeriodGraphs;
+use crate::graph::transaction_graph::{TransactionGraph, NodeId, EdgeId};
+
+//UnionFind, RollbackUnionFind, Components, weakly_connected_components, strongly_connected_components,
+//largest_components_over_slots, largest_connected_component, dfs_component_size, ComponentSummary, ComponentReport,
+//component_reports, display_component_reports, analyze_components
+
+/**************************************************************
+*
+*   The union-find (disjoint set) datastructure, union by size
+*   with path halving, used to merge the nodes joined by an edge
+*   in either direction into weakly connected components
+*
+***************************************************************/
+
+#[derive(Debug, Clone)]
+pub struct UnionFind {
+  parent: Vec<u32>,
+  size: Vec<usize>,
+}
+
+impl UnionFind {
+  pub fn new(n: usize) -> Self {
+      UnionFind {
+          parent: (0..n as u32).collect(),
+          size: vec![1; n],
+      }
+  }
+
+  pub fn find(&mut self, mut node: u32) -> u32 {
+      while self.parent[node as usize] != node {
+          let grandparent = self.parent[self.parent[node as usize] as usize];
+          self.parent[node as usize] = grandparent;
+          node = grandparent;
+      }
+      node
+  }
+
+  pub fn union(&mut self, a: u32, b: u32) -> bool {
+      let (mut root_a, mut root_b) = (self.find(a), self.find(b));
+      if root_a == root_b {
+          return false;
+      }
+      if self.size[root_a as usize] < self.size[root_b as usize] {
+          std::mem::swap(&mut root_a, &mut root_b);
+      }
+      self.parent[root_b as usize] = root_a;
+      self.size[root_a as usize] += self.size[root_b as usize];
+      true
+  }
+}
+
+/**************************************************************
+*
+*   The union-find with rollback, union by size without path
+*   compression so every union could be undone in reverse order.
+*   find is O(log n), snapshot returns a point to roll back to.
+*   The size of the largest set is kept on the history too
+*
+***************************************************************/
+
+#[derive(Debug, Clone)]
+pub struct RollbackUnionFind {
+  parent: Vec<u32>,
+  size: Vec<usize>,
+  largest: usize,
+  history: Vec<(u32, u32, usize)>,
+}
+
+impl RollbackUnionFind {
+  pub fn new(n: usize) -> Self {
+      RollbackUnionFind {
+          parent: (0..n as u32).collect(),
+          size: vec![1; n],
+          largest: if n > 0 { 1 } else { 0 },
+          history: Vec::new(),
+      }
+  }
+
+  pub fn find(&self, mut node: u32) -> u32 {
+      while self.parent[node as usize] != node {
+          node = self.parent[node as usize];
+      }
+      node
+  }
+
+  pub fn union(&mut self, a: u32, b: u32) -> bool {
+      let (mut root_a, mut root_b) = (self.find(a), self.find(b));
+      if root_a == root_b {
+          return false;
+      }
+      if self.size[root_a as usize] < self.size[root_b as usize] {
+          std::mem::swap(&mut root_a, &mut root_b);
+      }
+      self.history.push((root_a, root_b, self.largest));
+      self.parent[root_b as usize] = root_a;
+      self.size[root_a as usize] += self.size[root_b as usize];
+      self.largest = self.largest.max(self.size[root_a as usize]);
+      true
+  }
+
+  pub fn largest(&self) -> usize {
+      self.largest
+  }
+
+  pub fn snapshot(&self) -> usize {
+      self.history.len()
+  }
+
+  /**************************************************************
+  *
+  *   Undo every union made after the snapshot
+  *
+  ***************************************************************/
+
+  pub fn rollback(&mut self, snapshot: usize) {
+      while self.history.len() > snapshot {
+          let (root_a, root_b, largest) = self.history.pop().expect("history shorter than the snapshot");
+          self.parent[root_b as usize] = root_b;
+          self.size[root_a as usize] -= self.size[root_b as usize];
+          self.largest = largest;
+      }
+  }
+}
+
+/**************************************************************
+*
+*   The connected components of a graph:
+*   component_of: the component id of every node, indexed by NodeId
+*   sizes: the size of every component, indexed by component id
+*   The ids are ordered by size, component 0 is the largest, equal
+*   sizes are ordered by their smallest NodeId so the numbering is
+*   the same every run
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Components {
+  pub component_of: Vec<u32>,
+  pub sizes: Vec<usize>,
+}
+
+impl Components {
+  /**************************************************************
+  *
+  *   Renumber arbitrary component labels into the ordered ids
+  *
+  ***************************************************************/
+
+  fn from_labels(labels: &[u32]) -> Self {
+      let mut first_node: BTreeMap<u32, usize> = BTreeMap::new();
+      let mut label_size: BTreeMap<u32, usize> = BTreeMap::new();
+      for (node, &label) in labels.iter().enumerate() {
+          first_node.entry(label).or_insert(node);
+          *label_size.entry(label).or_default() += 1;
+      }
+
+      let mut order: Vec<(u32, usize, usize)> = label_size
+          .into_iter()
+          .map(|(label, size)| (label, size, first_node[&label]))
+          .collect();
+      order.sort_by(|a, b| b.1.cmp(&a.1).then(a.2.cmp(&b.2)));
+
+      let mut new_id: BTreeMap<u32, u32> = BTreeMap::new();
+      for (id, (label, _, _)) in order.iter().enumerate() {
+          new_id.insert(*label, id as u32);
+      }
+      Components {
+          component_of: labels.iter().map(|label| new_id[label]).collect(),
+          sizes: order.into_iter().map(|(_, size, _)| size).collect(),
+      }
+  }
+
+  pub fn count(&self) -> usize {
+      self.sizes.len()
+  }
+
+  pub fn largest_size(&self) -> usize {
+      self.sizes.first().copied().unwrap_or(0)
+  }
+
+  /**************************************************************
+  *
+  *   Histogram of component size -> number of components
+  *
+  ***************************************************************/
+
+  pub fn size_distribution(&self) -> BTreeMap<usize, usize> {
+      let mut distribution: BTreeMap<usize, usize> = BTreeMap::new();
+      for &size in &self.sizes {
+          *distribution.entry(size).or_default() += 1;
+      }
+      distribution
+  }
+
+  pub fn members(&self, component: u32) -> Vec<NodeId> {
+      self.component_of
+          .iter()
+          .enumerate()
+          .filter(|(_, &id)| id == component)
+          .map(|(node, _)| node as NodeId)
+          .collect()
+  }
+
+  /**************************************************************
+  *
+  *   The member addresses of the top_k largest components
+  *
+  ***************************************************************/
+
+  pub fn top_members(&self, graph: &TransactionGraph, top_k: usize) -> Vec<Vec<String>> {
+      let top_k = top_k.min(self.count());
+      let mut members: Vec<Vec<String>> = vec![Vec::new(); top_k];
+      for (node, &id) in self.component_of.iter().enumerate() {
+          if (id as usize) < top_k {
+              members[id as usize].push(graph.address(node as NodeId).to_string());
+          }
+      }
+      members
+  }
+}
+
+/**************************************************************
+*
+*   Compute the weakly connected components of given graph, two
+*   addresses are in the same component if they are joined by a
+*   path when the direction of the transfers is ignored
+*
+***************************************************************/
+
+pub fn weakly_connected_components(graph: &TransactionGraph) -> Components {
+  let mut union_find = UnionFind::new(graph.node_count());
+  for node in graph.nodes() {
+      for (neighbor, _) in graph.out_edges(node) {
+          union_find.union(node, neighbor);
+      }
+  }
+  let labels: Vec<u32> = graph.nodes().map(|node| union_find.find(node)).collect();
+  Components::from_labels(&labels)
+}
+
+/**************************************************************
+*
+*   Compute the strongly connected components of given graph with
+*   Tarjan's algorithm, two addresses are in the same component if
+*   each can reach the other along the direction of the transfers.
+*   The DFS keeps its own stack of (node, next edge) so the deep
+*   paths of a large graph do not overflow the call stack
+*
+***************************************************************/
+
+pub fn strongly_connected_components(graph: &TransactionGraph) -> Components {
+  let n = graph.node_count();
+  let mut state = TarjanState {
+      index: vec![UNVISITED; n],
+      low_link: vec![0; n],
+      on_stack: vec![false; n],
+      stack: Vec::new(),
+      labels: vec![0; n],
+      next_index: 0,
+      next_label: 0,
+  };
+
+  for root in graph.nodes() {
+      if state.index[root as usize] != UNVISITED {
+          continue;
+      }
+      let mut dfs: Vec<(NodeId, EdgeId, EdgeId)> = vec![state.visit(graph, root)];
+
+      while let Some((node, next_edge, end_edge)) = dfs.last_mut() {
+          let node = *node;
+          if *next_edge < *end_edge {
+              let neighbor = graph.edge_target(*next_edge);
+              *next_edge += 1;
+              if state.index[neighbor as usize] == UNVISITED {
+                  dfs.push(state.visit(graph, neighbor));
+              } else if state.on_stack[neighbor as usize] {
+                  state.low_link[node as usize] = state.low_link[node as usize].min(state.index[neighbor as usize]);
+              }
+              continue;
+          }
+
+          // All neighbours done, node is the root of a component if nothing on the stack is older
+          dfs.pop();
+          if let Some((parent, _, _)) = dfs.last() {
+              state.low_link[*parent as usize] = state.low_link[*parent as usize].min(state.low_link[node as usize]);
+          }
+          if state.low_link[node as usize] == state.index[node as usize] {
+              state.close_component(node);
+          }
+      }
+  }
+
+  Components::from_labels(&state.labels)
+}
+
+/**************************************************************
+*
+*   The bookkeeping of Tarjan's algorithm, index is the DFS
+*   discovery order and low_link the oldest node reachable
+*   that is still on the stack
+*
+***************************************************************/
+
+const UNVISITED: u32 = u32::MAX;
+
+struct TarjanState {
+  index: Vec<u32>,
+  low_link: Vec<u32>,
+  on_stack: Vec<bool>,
+  stack: Vec<NodeId>,
+  labels: Vec<u32>,
+  next_index: u32,
+  next_label: u32,
+}
+
+impl TarjanState {
+  // Returns the DFS frame of the node, its out-edges are next_edge..end_edge
+  fn visit(&mut self, graph: &TransactionGraph, node: NodeId) -> (NodeId, EdgeId, EdgeId) {
+      self.index[node as usize] = self.next_index;
+      self.low_link[node as usize] = self.next_index;
+      self.next_index += 1;
+      self.stack.push(node);
+      self.on_stack[node as usize] = true;
+      let first_edge = graph.out_edge_ids(node).next().unwrap_or(0);
+      (node, first_edge, first_edge + graph.out_degree(node) as EdgeId)
+  }
+
+  fn close_component(&mut self, root: NodeId) {
+      while let Some(member) = self.stack.pop() {
+          self.on_stack[member as usize] = false;
+          self.labels[member as usize] = self.next_label;
+          if member == root {
+              break;
+          }
+      }
+      self.next_label += 1;
+  }
+}
+
+/**************************************************************
+*
+*   Compute the size of the largest weakly connected component in
+*   every slot (e.g. window) 0..slots when every edge is alive over
+*   a range of slots, given as (source, target, first, end) with
+*   first <= slot < end. Offline dynamic connectivity: every range
+*   is added to the O(log slots) nodes of a segment tree that cover
+*   it, a DFS over the tree unions the edges of a node on the way
+*   down and rolls them back on the way up, so each leaf sees the
+*   union-find of exactly the edges alive in its slot. The result
+*   counts every node as a component of its own, 1 for a slot with
+*   nodes but no edges
+*
+***************************************************************/
+
+pub fn largest_components_over_slots(node_count: usize, edges: &[(NodeId, NodeId, usize, usize)], slots: usize) -> Vec<usize> {
+  if slots == 0 {
+      return Vec::new();
+  }
+  let mut tree: Vec<Vec<(NodeId, NodeId)>> = vec![Vec::new(); 4 * slots];
+  for &(source, target, first, end) in edges {
+      if first < end.min(slots) {
+          add_to_segment(&mut tree, 1, 0, slots, first, end.min(slots), (source, target));
+      }
+  }
+
+  let mut union_find = RollbackUnionFind::new(node_count);
+  let mut largest = vec![0; slots];
+  // Iterative DFS, a node is entered with its range and left to roll back its unions
+  let mut stack: Vec<(usize, usize, usize, Option<usize>)> = vec![(1, 0, slots, None)];
+  while let Some((index, low, high, entered)) = stack.pop() {
+      if let Some(snapshot) = entered {
+          union_find.rollback(snapshot);
+          continue;
+      }
+      let snapshot = union_find.snapshot();
+      for &(source, target) in &tree[index] {
+          union_find.union(source, target);
+      }
+      if high - low == 1 {
+          largest[low] = union_find.largest();
+          union_find.rollback(snapshot);
+          continue;
+      }
+      let middle = (low + high) / 2;
+      stack.push((index, low, high, Some(snapshot)));
+      stack.push((2 * index + 1, middle, high, None));
+      stack.push((2 * index, low, middle, None));
+  }
+  largest
+}
+
+/**************************************************************
+*
+*   Helper function to add an edge alive over first..end to the
+*   segment tree nodes covering that range
+*
+***************************************************************/
+
+fn add_to_segment(
+  tree: &mut [Vec<(NodeId, NodeId)>],
+  index: usize,
+  low: usize,
+  high: usize,
+  first: usize,
+  end: usize,
+  edge: (NodeId, NodeId),
+) {
+  if end <= low || high <= first {
+      return;
+  }
+  if first <= low && high <= end {
+      tree[index].push(edge);
+      return;
+  }
+  let middle = (low + high) / 2;
+  add_to_segment(tree, 2 * index, low, middle, first, end, edge);
+  add_to_segment(tree, 2 * index + 1, middle, high, first, end, edge);
+}
+
+/**************************************************************
+*
+*   Compute the larges connected component of given graph, the
+*   the result will be the size of largest weakly connected component
+*
+***************************************************************/
+
+pub fn largest_connected_component(graph: &TransactionGraph) -> usize {
+  weakly_connected_components(graph).largest_size()
+}
+
+/**************************************************************
+*
+*   The helper function used for calculation of the number of nodes
+*   reachable from start, the algorithm modifed from the DFS algorithm,
+*   it used stack to store the visited component and pop until all
+*   component is visited
+*
+***************************************************************/
+
+pub fn dfs_component_size(
+  graph: &TransactionGraph,
+  start: NodeId,
+  visited: &mut [bool],
+) -> usize {
+  let mut stack = vec![start];
+  let mut size = 0;
+
+  while let Some(node) = stack.pop() {
+      if !visited[node as usize] {
+          visited[node as usize] = true;
+          size += 1;
+          for (neighbor, _) in graph.out_edges(node) {
+              if !visited[neighbor as usize] {
+                  stack.push(neighbor);
+              }
+          }
+      }
+  }
+
+  size
+}
+
+/**************************************************************
+*
+*   The summary of the components of one kind of a graph:
+*   count, largest: the number of components and the largest size
+*   size_distribution: component size -> number of components
+*   top_members: the member addresses of the top_k largest ones
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentSummary {
+  pub count: usize,
+  pub largest: usize,
+  pub size_distribution: BTreeMap<usize, usize>,
+  pub top_members: Vec<Vec<String>>,
+}
+
+impl ComponentSummary {
+  pub fn new(graph: &TransactionGraph, components: &Components, top_k: usize) -> Self {
+      ComponentSummary {
+          count: components.count(),
+          largest: components.largest_size(),
+          size_distribution: components.size_distribution(),
+          top_members: components.top_members(graph, top_k),
+      }
+  }
+}
+
+/**************************************************************
+*
+*   The weakly and strongly connected components of the graph
+*   of one period
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ComponentReport {
+  pub period: String,
+  pub weak: ComponentSummary,
+  pub strong: ComponentSummary,
+}
+
+pub fn component_reports(graphs: &PeriodGraphs, top_k: usize) -> Vec<ComponentReport> {
+  graphs
+      .iter()
+      .map(|(period, graph)| ComponentReport {
+          period: period.clone(),
+          weak: ComponentSummary::new(graph, &weakly_connected_components(graph), top_k),
+          strong: ComponentSummary::new(graph, &strongly_connected_components(graph), top_k),
+      })
+      .collect()
+}
+
+/**************************************************************
+*
+*   Helper function to print the components of one kind, the
+*   member lists are cut after a few addresses to keep the output
+*   readable
+*
+***************************************************************/
+
+fn display_components(summary: &ComponentSummary, kind: &str) {
+  const SHOWN_MEMBERS: usize = 10;
+  println!("  {} components: {}, largest: {}", kind, summary.count, summary.largest);
+  println!("  {} size distribution: {:?}", kind, summary.size_distribution);
+  for (id, members) in summary.top_members.iter().enumerate() {
+      let shown: Vec<&String> = members.iter().take(SHOWN_MEMBERS).collect();
+      let hidden = members.len().saturating_sub(SHOWN_MEMBERS);
+      if hidden > 0 {
+          println!("  {} #{} ({} addresses): {:?} and {} more", kind, id, members.len(), shown, hidden);
+      } else {
+          println!("  {} #{} ({} addresses): {:?}", kind, id, members.len(), shown);
+      }
+  }
+}
+
+/**************************************************************
+*
+*   Print the components of every period on the console
+*
+***************************************************************/
+
+pub fn display_component_reports(reports: &[ComponentReport]) {
+  println!("Connected Components:");
+  for report in reports {
+      println!("[{}]", report.period);
+      display_components(&report.weak, "Weakly connected");
+      display_components(&report.strong, "Strongly connected");
+  }
+}
+
+/**************************************************************
+*
+*   A wrapper function for convenience to be called in main.rs
+*   which analyze and display the weakly and strongly connected
+*   components of the graph of every period, with the members of
+*   the top_k largest components
+*
+***************************************************************/
+
+pub fn analyze_components(graphs: &PeriodGraphs, top_k: usize) {
+  display_component_reports(&component_reports(graphs, top_k));
+}
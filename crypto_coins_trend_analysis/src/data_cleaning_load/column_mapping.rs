@@ -0,0 +1,287 @@
+use std::path::Path;
+use csv::StringRecord;
+use serde::Deserialize;
+
+//ColumnMapping, ColumnIndices, parse_timestamp, format_timestamp, parse_value
+
+/**************************************************************
+*
+*   Maps the five fields the loader needs to the header names of
+*   an export, every field lists the accepted names in order of
+*   preference, the header is matched ignoring case and whitespace.
+*   It could be loaded from a TOML or JSON file of the form:
+*
+*   from = ["sender"]
+*   to = ["receiver"]
+*   timestamp = ["block_time"]
+*   token = ["token"]
+*   value = ["amount"]
+*
+*   or picked from the presets of common blockchain exports
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ColumnMapping {
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+    pub timestamp: Vec<String>,
+    pub token: Vec<String>,
+    pub value: Vec<String>,
+}
+
+/**************************************************************
+*
+*   The positions of the mapped fields in the records of one file
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnIndices {
+    pub from: usize,
+    pub to: usize,
+    pub timestamp: usize,
+    pub token: usize,
+    pub value: usize,
+}
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+impl Default for ColumnMapping {
+    fn default() -> Self {
+        ColumnMapping::chartalist()
+    }
+}
+
+impl ColumnMapping {
+    /**************************************************************
+    *
+    *   The Chartalist stablecoin ERC20 dataset used by this project,
+    *   the short names are accepted too for hand-written files
+    *
+    ***************************************************************/
+
+    pub fn chartalist() -> Self {
+        ColumnMapping {
+            from: names(&["from_address", "from"]),
+            to: names(&["to_address", "to"]),
+            timestamp: names(&["time_stamp", "timestamp"]),
+            token: names(&["contract_address", "contract"]),
+            value: names(&["value"]),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   The token transfer CSV download of Etherscan
+    *
+    ***************************************************************/
+
+    pub fn etherscan() -> Self {
+        ColumnMapping {
+            from: names(&["From"]),
+            to: names(&["To"]),
+            timestamp: names(&["UnixTimestamp", "DateTime (UTC)", "DateTime"]),
+            token: names(&["ContractAddress"]),
+            value: names(&["TokenValue", "Value"]),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   The BigQuery crypto_ethereum.token_transfers table
+    *
+    ***************************************************************/
+
+    pub fn bigquery() -> Self {
+        ColumnMapping {
+            from: names(&["from_address"]),
+            to: names(&["to_address"]),
+            timestamp: names(&["block_timestamp"]),
+            token: names(&["token_address"]),
+            value: names(&["value"]),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   The Dune erc20_ethereum.evt_Transfer table, and the amount
+    *   and block_time columns of tokens.transfers
+    *
+    ***************************************************************/
+
+    pub fn dune() -> Self {
+        ColumnMapping {
+            from: names(&["from"]),
+            to: names(&["to"]),
+            timestamp: names(&["evt_block_time", "block_time"]),
+            token: names(&["contract_address"]),
+            value: names(&["value", "amount"]),
+        }
+    }
+
+    pub fn preset(name: &str) -> Result<Self, String> {
+        match name {
+            "chartalist" => Ok(ColumnMapping::chartalist()),
+            "etherscan" => Ok(ColumnMapping::etherscan()),
+            "bigquery" => Ok(ColumnMapping::bigquery()),
+            "dune" => Ok(ColumnMapping::dune()),
+            _ => Err(format!(
+                "unknown column preset '{}', expected chartalist, etherscan, bigquery, dune or a .toml/.json file",
+                name
+            )),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   Load the column mapping from a file, the format is chosen by
+    *   the extension (.toml or .json)
+    *
+    ***************************************************************/
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Ok(toml::from_str(&content)?),
+            Some("json") => Ok(serde_json::from_str(&content)?),
+            _ => Err(format!("unsupported column mapping file '{}', expected .toml or .json", path).into()),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   Resolve the mapping against the header of a file, the error
+    *   names the field that could not be found
+    *
+    ***************************************************************/
+
+    pub fn resolve(&self, header: &StringRecord) -> Result<ColumnIndices, String> {
+        let normalize = |name: &str| name.trim().trim_start_matches('\u{feff}').to_lowercase();
+        let header: Vec<String> = header.iter().map(normalize).collect();
+        let find = |field: &str, accepted: &[String]| -> Result<usize, String> {
+            accepted
+                .iter()
+                .find_map(|name| header.iter().position(|column| *column == normalize(name)))
+                .ok_or_else(|| {
+                    format!(
+                        "missing column for '{}', expected one of [{}] but the header is [{}]",
+                        field,
+                        accepted.join(", "),
+                        header.join(", ")
+                    )
+                })
+        };
+        Ok(ColumnIndices {
+            from: find("from", &self.from)?,
+            to: find("to", &self.to)?,
+            timestamp: find("timestamp", &self.timestamp)?,
+            token: find("token", &self.token)?,
+            value: find("value", &self.value)?,
+        })
+    }
+}
+
+/**************************************************************
+*
+*   Helper function to count the days since 1970-01-01 of a civil
+*   date, Howard Hinnant's days_from_civil algorithm, None when the
+*   year is too far out to count
+*
+***************************************************************/
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> Option<i64> {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era.checked_mul(146097)?.checked_add(day_of_era - 719468)
+}
+
+/**************************************************************
+*
+*   Parse a timestamp into unix epoch seconds, either the epoch
+*   seconds themselves or a UTC date time such as
+*   "2022-05-09 12:30:00", "2022-05-09T12:30:00Z" or
+*   "2022-05-09 12:30:00.000 UTC", fractions of seconds are dropped
+*
+***************************************************************/
+
+pub fn parse_timestamp(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Ok(seconds);
+    }
+    let invalid = || format!("invalid timestamp '{}', expected epoch seconds or YYYY-MM-DD HH:MM:SS", value);
+
+    let value = value.trim_end_matches(" UTC").trim_end_matches('Z').trim_end_matches("+00:00");
+    let (date, time) = value.split_once([' ', 'T']).unwrap_or((value, "00:00:00"));
+    let date: Vec<i64> = date.split('-').map(|part| part.parse()).collect::<Result<_, _>>().map_err(|_| invalid())?;
+    let time = time.split('.').next().unwrap_or(time);
+    let time: Vec<i64> = time.split(':').map(|part| part.parse()).collect::<Result<_, _>>().map_err(|_| invalid())?;
+    if date.len() != 3 || !(2..=3).contains(&time.len()) {
+        return Err(invalid());
+    }
+    let (month, day) = (date[1], date[2]);
+    let (hour, minute, second) = (time[0], time[1], time.get(2).copied().unwrap_or(0));
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return Err(invalid());
+    }
+
+    let seconds = days_from_civil(date[0], month, day)
+        .and_then(|days| days.checked_mul(86400))
+        .and_then(|seconds| seconds.checked_add(hour * 3600 + minute * 60 + second))
+        .ok_or_else(invalid)?;
+    u64::try_from(seconds).map_err(|_| invalid())
+}
+
+/**************************************************************
+*
+*   Helper function for the civil date of a count of days since
+*   1970-01-01, Howard Hinnant's civil_from_days algorithm
+*
+***************************************************************/
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/**************************************************************
+*
+*   Format unix epoch seconds as a UTC date time "2022-05-09 12:30:00"
+*
+***************************************************************/
+
+pub fn format_timestamp(timestamp: u64) -> String {
+    let seconds = timestamp as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86400));
+    let time = seconds.rem_euclid(86400);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+/**************************************************************
+*
+*   Parse a transfer value, Etherscan formats large values with
+*   thousands separators such as "1,250.5"
+*
+***************************************************************/
+
+pub fn parse_value(value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .replace(',', "")
+        .parse::<f64>()
+        .map_err(|_| format!("invalid value '{}'", value))
+}
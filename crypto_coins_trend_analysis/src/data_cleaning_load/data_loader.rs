@@ -1,145 +1,333 @@
-use std::collections::HashMap;
-use csv::ReaderBuilder;
-
-//Transaction, load_csv_convert_graph, display_graph
-
-/**************************************************************
-*
-*   The datastructure defined to represent the weight of the graph
-*   which includes three fields:
-*   value: represent the numerical value used in transaction
-*   unit: the coin used in this transaction, also unit of value
-*   timestamp: the time of transaction executed, express in unix epoch time
-*
-***************************************************************/
-
-#[derive(Clone)] 
-pub struct Transaction {
-  pub value: f64,           //value of transaction
-  pub unit: String,         //coin used
-  pub timestamp: u64,       //time of transaction, if time1 < time2, then time1 is earlier than time2
-}
-
-/**************************************************************
-*
-*   The constructor of a Transaction type instances
-*
-***************************************************************/
-
-impl Transaction {
-  pub fn new(value: f64, unit:String, timestamp: u64) -> Self {
-      Transaction {
-          value,
-          unit,
-          timestamp,
-      }
-  }
-}
-
-/**************************************************************
-*
-*   Formatted the Transaction datastructure, so that it could be
-*   printed
-*
-***************************************************************/
-
-impl std::fmt::Debug for Transaction {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(
-            f,
-            "Transaction {{ value: {}, unit: {}, timestamp: {} }}",
-            self.value, self.unit, self.timestamp
-        )
-    }
-}
-
-/**************************************************************
-*
-*   The function that load the dataset and convert into weighted graph
-*   the weighted graph is defined as:
-*   HashMap<String, HashMap<String, Transaction>>
-*   The key(node) in hashmap is the from_address in dataset, which represented
-*   in String type, the value of outer hashmap represent the outdegree of
-*   of current key(node), the inner hashmap the store the weight and node that
-*   outer key pointed to.
-*
-***************************************************************/
-
-pub fn load_csv_convert_graph(
-  path: &str,
-) -> Result<(HashMap<String, HashMap<String, Transaction>>,
-           HashMap<String, HashMap<String, Transaction>>,
-           HashMap<String, HashMap<String, Transaction>>),
-          Box<dyn std::error::Error>> 
-{
-  let mut rdr = ReaderBuilder::new().from_path(path)?;
-
-  let start_time: u64 = 1651104000;       // Start time
-  let during_crash_time: u64 = 1651708800; // During crash start
-  let after_crash_time: u64 = 1652400000; // After crash start
-  let end_time: u64 = 1652918400;         // End time
-
-  let mut graph_prior_crash: HashMap<String, HashMap<String, Transaction>> = HashMap::new();
-  let mut graph_during_crash: HashMap<String, HashMap<String, Transaction>> = HashMap::new();
-  let mut graph_after_crash: HashMap<String, HashMap<String, Transaction>> = HashMap::new();
-
-  let mut count_before = 0;
-  let mut count_during = 0;
-  let mut count_after = 0;
-  for result in rdr.records() {
-      let record = result?;
-
-      let from_address: String = record[2].to_string();
-      let to_address: String = record[3].to_string();
-      let timestamp: u64 = record[4].parse::<u64>()?;
-      let contract_address: String = record[5].to_string();
-      let value: f64 = record[6].parse::<f64>()?;
-
-      let transaction = Transaction::new(value, contract_address, timestamp);
-
-      if timestamp >= start_time && timestamp < during_crash_time && count_before < 10000 {
-          graph_prior_crash
-              .entry(from_address.clone())
-              .or_insert_with(HashMap::new)
-              .insert(to_address.clone(), transaction.clone());
-          count_before += 1;
-      }
-      else if timestamp >= during_crash_time && timestamp < after_crash_time && count_during < 10000 {
-          graph_during_crash
-              .entry(from_address.clone())
-              .or_insert_with(HashMap::new)
-              .insert(to_address.clone(), transaction.clone());
-          count_during += 1;
-      }
-      else if timestamp >= after_crash_time && timestamp < end_time && count_after < 10000 {
-          graph_after_crash
-              .entry(from_address.clone())
-              .or_insert_with(HashMap::new)
-              .insert(to_address.clone(), transaction.clone());
-          count_after += 1;
-      }
-  }
-  println!("finished data loading......");
-  Ok((graph_prior_crash, graph_during_crash, graph_after_crash))
-}
-
-/**************************************************************
-*
-*   The helper function to display the defined graph in a more
-*   human-readable way
-*
-***************************************************************/
-
-pub fn display_graph(graph: &HashMap<String, HashMap<String, Transaction>>, graph_name: &str) {
-  println!("Graph: {}", graph_name);
-  for (from_address, connections) in graph {
-      println!("  From: {}", from_address);
-      for (to_address, transaction) in connections {
-          println!(
-              "    To: {}, Value: {}, Unit: {}, Timestamp: {}",
-              to_address, transaction.value, transaction.unit, transaction.timestamp
-          );
-      }
-  }
-  println!();
-}
\ No newline at end of file
+use csv::{ReaderBuilder, StringRecord};
+use indexmap::IndexMap;
+use crate::data_cleaning_load::period_config::PeriodSpec;
+use crate::data_cleaning_load::sampling::{PeriodSampler, Sampling};
+use crate::data_cleaning_load::input_stream::{open_input, Progress};
+use crate::data_cleaning_load::column_mapping::ColumnMapping;
+use crate::data_cleaning_load::token_registry::TokenRegistry;
+use crate::data_cleaning_load::price_table::{PriceTable, ValueBasis};
+use crate::data_cleaning_load::validation::{check_record, CheckedTransfer, LoadSummary, QuarantineWriter, Validation, ValidationMode};
+use crate::graph::transaction_graph::TransactionGraph;
+
+//Transaction, PeriodGraphs, LoadOptions, stream_transfers, load_csv_with_summary, load_csv_convert_graph, display_graph
+
+/**************************************************************
+*
+*   The datastructure defined to represent the weight of the graph
+*   which includes three fields:
+*   value: represent the numerical value used in transaction
+*   unit: the coin used in this transaction, also unit of value
+*   timestamp: the time of transaction executed, express in unix epoch time
+*   usd_value: the value in USD at the time of transaction, if a price is known
+*
+***************************************************************/
+
+#[derive(Clone)] 
+pub struct Transaction {
+  pub value: f64,           //value of transaction
+  pub unit: String,         //coin used
+  pub timestamp: u64,       //time of transaction, if time1 < time2, then time1 is earlier than time2
+  pub usd_value: Option<f64>, //value in USD, None without a price
+}
+
+/**************************************************************
+*
+*   The constructor of a Transaction type instances
+*
+***************************************************************/
+
+impl Transaction {
+  pub fn new(value: f64, unit:String, timestamp: u64) -> Self {
+      Transaction {
+          value,
+          unit,
+          timestamp,
+          usd_value: None,
+      }
+  }
+
+  pub fn with_usd_value(mut self, usd_value: Option<f64>) -> Self {
+      self.usd_value = usd_value;
+      self
+  }
+}
+
+/**************************************************************
+*
+*   Formatted the Transaction datastructure, so that it could be
+*   printed
+*
+***************************************************************/
+
+impl std::fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Transaction {{ value: {}, unit: {}, timestamp: {}, usd_value: {:?} }}",
+            self.value, self.unit, self.timestamp, self.usd_value
+        )
+    }
+}
+
+/**************************************************************
+*
+*   PeriodGraphs maps each period name to its transaction graph,
+*   in the order the periods are defined
+*
+***************************************************************/
+
+pub type PeriodGraphs = IndexMap<String, TransactionGraph>;
+
+/**************************************************************
+*
+*   The options of loading the dataset:
+*   sampling: which transfers of every period are kept, all by default
+*   seed: the seed of the random sampling modes, every period draws
+*   from its own stream derived from it
+*   chunk_size: the number of rows read and processed at a time,
+*   the row buffers are reused so memory does not grow with the file
+*   progress: report the progress to stderr after every chunk and
+*   when the load is done
+*   columns: the header names of the fields, the Chartalist dataset
+*   by default
+*   validation: what is done with bad rows, by default the first
+*   unparsable row aborts the load
+*   tokens: the token registry naming the contract addresses
+*   normalize_values: divide the raw amounts of the known tokens by
+*   10^decimals so different tokens are in comparable units, off by
+*   default as the Chartalist values are already in whole tokens
+*   prices: the historical USD prices used to value the transfers
+*   value_basis: whether the graphs hold token amounts or USD values,
+*   with USD the transfers without a price are left out
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadOptions {
+  pub sampling: Sampling,
+  pub seed: u64,
+  pub chunk_size: usize,
+  pub progress: bool,
+  pub columns: ColumnMapping,
+  pub validation: Validation,
+  pub tokens: TokenRegistry,
+  pub normalize_values: bool,
+  pub prices: PriceTable,
+  pub value_basis: ValueBasis,
+}
+
+impl Default for LoadOptions {
+  fn default() -> Self {
+      LoadOptions {
+          sampling: Sampling::All,
+          seed: 42,
+          chunk_size: 100_000,
+          progress: true,
+          columns: ColumnMapping::chartalist(),
+          validation: Validation::default(),
+          tokens: TokenRegistry::builtin(),
+          normalize_values: false,
+          prices: PriceTable::new(),
+          value_basis: ValueBasis::Token,
+      }
+  }
+}
+
+/**************************************************************
+*
+*   Helper function to turn a checked row into a Transaction, the
+*   value normalised by the decimals of its token if asked and
+*   annotated with the USD value of the nearest prior price
+*
+***************************************************************/
+
+fn annotate_transaction(transfer: &CheckedTransfer, options: &LoadOptions) -> Transaction {
+  let value = if options.normalize_values {
+      options.tokens.normalize(transfer.token, transfer.value)
+  } else {
+      transfer.value
+  };
+  let usd_value = options.prices.usd_value(&options.tokens, transfer.token, value, transfer.timestamp);
+  Transaction::new(value, transfer.token.to_string(), transfer.timestamp).with_usd_value(usd_value)
+}
+
+/**************************************************************
+*
+*   The function that reads the dataset in one pass and hands every
+*   accepted transfer to visit as (row, from, to, transaction, value),
+*   row counts the data rows from 0 and value is the transaction value
+*   in the selected value basis. The input may be gzip or zstd
+*   compressed and is streamed in chunks so the whole export never
+*   has to fit in memory. The fields are found by their header names,
+*   the timestamp may be epoch seconds or a UTC date time. With
+*   validation every row is checked and bad rows are counted in the
+*   returned summary instead of aborting the load. With
+*   normalize_values the values are divided by the decimals of their
+*   token, with a price table
+*   every transaction is valued in USD at its timestamp. An error
+*   returned by visit stops the load and is returned
+*
+***************************************************************/
+
+pub fn stream_transfers<F>(
+  path: &str,
+  options: &LoadOptions,
+  mut visit: F,
+) -> Result<LoadSummary, Box<dyn std::error::Error>>
+where
+  F: FnMut(usize, &str, &str, &Transaction, f64) -> Result<(), Box<dyn std::error::Error>>,
+{
+  let input = open_input(path)?;
+  let progress = Progress::new(&input, options.progress);
+  let validating = options.validation.mode != ValidationMode::Off;
+  // Short rows are reported by check_record in row order, not by the reader ahead of the chunk
+  let mut rdr = ReaderBuilder::new().flexible(true).from_reader(input.reader);
+  let columns = options.columns.resolve(rdr.headers()?)?;
+  let mut quarantine = match &options.validation.mode {
+      ValidationMode::Quarantine(quarantine_path) => Some(QuarantineWriter::create(quarantine_path, rdr.headers()?)?),
+      _ => None,
+  };
+  let mut summary = LoadSummary::default();
+
+  let mut chunk: Vec<StringRecord> = vec![StringRecord::new(); options.chunk_size.max(1)];
+  let mut row = 0;
+  loop {
+      let mut filled = 0;
+      while filled < chunk.len() && rdr.read_record(&mut chunk[filled])? {
+          filled += 1;
+      }
+
+      for record in &chunk[..filled] {
+          row += 1;
+          summary.rows_read += 1;
+          let transfer = match check_record(record, &columns, &options.validation) {
+              Ok(transfer) => transfer,
+              Err(rejection) if !validating => return Err(format!("row {}: {}", row, rejection.message).into()),
+              Err(rejection) => {
+                  summary.reject(rejection.reason);
+                  if let Some(writer) = quarantine.as_mut() {
+                      writer.write(row, record, &rejection)?;
+                  }
+                  continue;
+              }
+          };
+          summary.accepted += 1;
+          let transaction = annotate_transaction(&transfer, options);
+          let value = match (options.value_basis, transaction.usd_value) {
+              (ValueBasis::Token, _) => transaction.value,
+              (ValueBasis::Usd, Some(usd_value)) => usd_value,
+              (ValueBasis::Usd, None) => {
+                  summary.unpriced += 1;
+                  continue;
+              }
+          };
+          visit(row - 1, transfer.from_address, transfer.to_address, &transaction, value)?;
+      }
+      if filled > 0 {
+          progress.report(row);
+      }
+      if filled < chunk.len() {
+          break;
+      }
+  }
+  if let Some(writer) = quarantine {
+      writer.finish()?;
+  }
+  if options.progress {
+      eprintln!("finished data loading......");
+  }
+  Ok(summary)
+}
+
+/**************************************************************
+*
+*   The function that load the dataset and convert into one weighted
+*   transaction graph for every period of the given period specification,
+*   a transaction is offered to every period whose range contains its
+*   timestamp and kept according to the sampling mode, the tokens of
+*   the graphs are labelled with their registry symbol. With progress
+*   the transfers kept of every period are reported to stderr
+*
+***************************************************************/
+
+pub fn load_csv_with_summary(
+  path: &str,
+  spec: &PeriodSpec,
+  options: &LoadOptions,
+) -> Result<(PeriodGraphs, LoadSummary), Box<dyn std::error::Error>>
+{
+  let mut samplers: Vec<PeriodSampler> = (0..spec.periods.len())
+      .map(|index| PeriodSampler::new(options.sampling, options.seed.wrapping_add(index as u64)))
+      .collect();
+
+  let summary = stream_transfers(path, options, |row, from_address, to_address, transaction, value| {
+      for (index, period) in spec.periods.iter().enumerate() {
+          if period.contains(transaction.timestamp) {
+              samplers[index].offer(row, from_address, to_address, &transaction.unit, value, transaction.timestamp);
+          }
+      }
+      Ok(())
+  })?;
+
+  if options.progress {
+      for (period, sampler) in spec.periods.iter().zip(&samplers) {
+          eprintln!("  [{}] kept {} of {} transfers", period.name, sampler.kept(), sampler.offered());
+      }
+  }
+  let graphs = spec
+      .periods
+      .iter()
+      .zip(samplers)
+      .map(|(period, sampler)| {
+          let mut graph = sampler.build();
+          for token in graph.tokens() {
+              let symbol = options.tokens.symbol(graph.token(token)).to_string();
+              graph.set_token_symbol(token, &symbol);
+          }
+          (period.name.clone(), graph)
+      })
+      .collect();
+  Ok((graphs, summary))
+}
+
+/**************************************************************
+*
+*   Load the graphs of every period without the load summary
+*
+***************************************************************/
+
+pub fn load_csv_convert_graph(
+  path: &str,
+  spec: &PeriodSpec,
+  options: &LoadOptions,
+) -> Result<PeriodGraphs, Box<dyn std::error::Error>>
+{
+  load_csv_with_summary(path, spec, options).map(|(graphs, _)| graphs)
+}
+
+/**************************************************************
+*
+*   The helper function to display the defined graph in a more
+*   human-readable way
+*
+***************************************************************/
+
+pub fn display_graph(graph: &TransactionGraph, graph_name: &str) {
+  println!("Graph: {}", graph_name);
+  for node in graph.nodes() {
+      if graph.out_degree(node) == 0 {
+          continue;
+      }
+      println!("  From: {}", graph.address(node));
+      for (target, edge) in graph.out_edges(node) {
+          println!(
+              "    To: {}, Transfers: {}, Total Value: {}, First: {}, Last: {}",
+              graph.address(target), edge.count, edge.total_value, edge.first_timestamp, edge.last_timestamp
+          );
+          for (token, flow) in &edge.tokens {
+              println!("      Unit: {}, Transfers: {}, Value: {}", graph.token_symbol(*token), flow.count, flow.value);
+          }
+      }
+  }
+  println!();
+}
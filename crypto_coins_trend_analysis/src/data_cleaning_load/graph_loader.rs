@@ -0,0 +1,713 @@
+use std::collections::HashMap;
+use csv::{ReaderBuilder, StringRecord, Trim};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use crate::data_cleaning_load::data_loader::{self, LoadOptions, PeriodGraphs, Transaction};
+use crate::data_cleaning_load::column_mapping::{parse_timestamp, parse_value, ColumnMapping};
+use crate::data_cleaning_load::input_stream::open_input;
+use crate::data_cleaning_load::period_config::PeriodSpec;
+use crate::data_cleaning_load::price_table::ValueBasis;
+use crate::data_cleaning_load::sampling::Sampling;
+use crate::data_cleaning_load::validation::{check_transfer, LoadSummary, QuarantineWriter, RejectReason, Rejection, ValidationMode};
+use crate::graph::transaction_graph::{GraphBuilder, TokenFlow, TransactionGraph};
+
+//Flow, GraphLoader, InputFormat, loader_for, TransferCsvLoader, EdgeListLoader, JsonLinesLoader, GraphMlLoader, ParquetLoader
+
+/**************************************************************
+*
+*   One record of a graph source, a single transfer or the
+*   aggregated transfers of one token between two addresses:
+*   from, to: the addresses
+*   token: the token contract address or symbol
+*   flow: the number, value and time of the transfers
+*   timed: false when the source has no time, such a flow belongs
+*   to every period
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Flow {
+    pub from: String,
+    pub to: String,
+    pub token: String,
+    pub flow: TokenFlow,
+    pub timed: bool,
+}
+
+/**************************************************************
+*
+*   A source of transfers, whatever the file format the same
+*   TransactionGraph is built from it so every analysis works on
+*   any source. An implementation only reads its records, the
+*   provided methods check, normalise and assign them to periods:
+*
+*   read_flows: offer every record with its 0-based row to visit,
+*   Err for a record that could not be read
+*   load_graph: one graph with every flow
+*   load_periods: the graph of every period, a flow belongs to the
+*   periods containing its first timestamp
+*
+*   With validation unreadable records are counted as malformed
+*   rows of the summary, otherwise the first one aborts the load.
+*   The records read are checked like the rows of the transfer csv,
+*   a record with a bad address, value or timestamp or sending to
+*   itself is counted under its reason and dropped or quarantined.
+*   With normalize_values the values are divided by the token
+*   decimals like the transfer csv, sampling and USD values need
+*   the individual transfers of the csv and are only supported by
+*   TransferCsvLoader
+*
+***************************************************************/
+
+pub trait GraphLoader {
+    fn read_flows(
+        &self,
+        options: &LoadOptions,
+        visit: &mut dyn FnMut(usize, Result<Flow, String>) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>>;
+
+    fn load_graph(&self, options: &LoadOptions) -> Result<(TransactionGraph, LoadSummary), Box<dyn std::error::Error>> {
+        let mut builder = GraphBuilder::new();
+        let summary = read_checked(self, options, &mut |flow| builder.add_token_flow(&flow.from, &flow.to, &flow.token, &flow.flow))?;
+        Ok((name_tokens(builder.build(), options), summary))
+    }
+
+    fn load_periods(&self, spec: &PeriodSpec, options: &LoadOptions) -> Result<(PeriodGraphs, LoadSummary), Box<dyn std::error::Error>> {
+        if options.sampling != Sampling::All || options.value_basis == ValueBasis::Usd {
+            return Err("sampling and USD values are only supported for the transfer csv input".into());
+        }
+        let mut builders = vec![GraphBuilder::new(); spec.periods.len()];
+        let summary = read_checked(self, options, &mut |flow| {
+            for (period, builder) in spec.periods.iter().zip(builders.iter_mut()) {
+                if !flow.timed || period.contains(flow.flow.first_timestamp) {
+                    builder.add_token_flow(&flow.from, &flow.to, &flow.token, &flow.flow);
+                }
+            }
+        })?;
+        let graphs = spec
+            .periods
+            .iter()
+            .zip(builders)
+            .map(|(period, builder)| (period.name.clone(), name_tokens(builder.build(), options)))
+            .collect();
+        Ok((graphs, summary))
+    }
+}
+
+/**************************************************************
+*
+*   Helper function to read the flows of a source with the
+*   validation and value normalisation of the load options, the
+*   flows are checked by the same check_transfer as the transfer
+*   csv rows so a validation mode means the same for every format.
+*   A quarantined record is written with the fields of its flow,
+*   a malformed one with empty fields and the parse error
+*
+***************************************************************/
+
+fn read_checked<L: GraphLoader + ?Sized>(
+    loader: &L,
+    options: &LoadOptions,
+    add: &mut dyn FnMut(Flow),
+) -> Result<LoadSummary, Box<dyn std::error::Error>> {
+    let validating = options.validation.mode != ValidationMode::Off;
+    let mut quarantine = match &options.validation.mode {
+        ValidationMode::Quarantine(path) => Some(QuarantineWriter::create(path, &StringRecord::from(QUARANTINE_HEADER.to_vec()))?),
+        _ => None,
+    };
+    let mut summary = LoadSummary::default();
+    loader.read_flows(options, &mut |row, flow| {
+        summary.rows_read += 1;
+        let checked = match flow {
+            Ok(flow) if validating => {
+                let timestamps = if flow.timed { vec![flow.flow.first_timestamp, flow.flow.last_timestamp] } else { Vec::new() };
+                let values = [flow.flow.value, flow.flow.min_value, flow.flow.max_value];
+                match check_transfer(&flow.from, &flow.to, &values, &timestamps, &options.validation) {
+                    Ok(()) => Ok(flow),
+                    Err(rejection) => Err((flow_record(&flow), rejection)),
+                }
+            }
+            Ok(flow) => Ok(flow),
+            Err(message) if !validating => return Err(format!("row {}: {}", row + 1, message).into()),
+            Err(message) => Err((StringRecord::from(vec![""; QUARANTINE_HEADER.len()]), Rejection { reason: RejectReason::MalformedRow, message })),
+        };
+        let mut flow = match checked {
+            Ok(flow) => flow,
+            Err((record, rejection)) => {
+                summary.reject(rejection.reason);
+                if let Some(writer) = quarantine.as_mut() {
+                    writer.write(row + 1, &record, &rejection)?;
+                }
+                return Ok(());
+            }
+        };
+        summary.accepted += 1;
+        if options.normalize_values {
+            let scale = options.tokens.normalize(&flow.token, 1.0);
+            flow.flow.value *= scale;
+            flow.flow.min_value *= scale;
+            flow.flow.max_value *= scale;
+        }
+        add(flow);
+        Ok(())
+    })?;
+    if let Some(writer) = quarantine {
+        writer.finish()?;
+    }
+    Ok(summary)
+}
+
+const QUARANTINE_HEADER: [&str; 9] =
+    ["source", "target", "token", "transfers", "value", "min_value", "max_value", "first_timestamp", "last_timestamp"];
+
+fn flow_record(flow: &Flow) -> StringRecord {
+    let timestamp = |timestamp: u64| if flow.timed { timestamp.to_string() } else { String::new() };
+    StringRecord::from(vec![
+        flow.from.clone(),
+        flow.to.clone(),
+        flow.token.clone(),
+        flow.flow.count.to_string(),
+        flow.flow.value.to_string(),
+        flow.flow.min_value.to_string(),
+        flow.flow.max_value.to_string(),
+        timestamp(flow.flow.first_timestamp),
+        timestamp(flow.flow.last_timestamp),
+    ])
+}
+
+fn name_tokens(mut graph: TransactionGraph, options: &LoadOptions) -> TransactionGraph {
+    for token in graph.tokens() {
+        let symbol = options.tokens.symbol(graph.token(token)).to_string();
+        graph.set_token_symbol(token, &symbol);
+    }
+    graph
+}
+
+/**************************************************************
+*
+*   The fields of a record, found by the names of the column
+*   mapping followed by the names common in graph files:
+*   source/target for the addresses, weight for the value and
+*   transfers, min_value, max_value, first_timestamp and
+*   last_timestamp of aggregated edges such as the edge lists and
+*   GraphML written by export. Only from and to are required, a
+*   record without a value weighs 1, without a token it is in the
+*   token "unknown" and without a timestamp it has no time. When
+*   the smallest and largest transfer of an aggregated record are
+*   not given, both are set to the mean transfer
+*
+***************************************************************/
+
+const FIELDS: usize = 9;
+const FROM: usize = 0;
+const TO: usize = 1;
+const TOKEN: usize = 2;
+const VALUE: usize = 3;
+const TIMESTAMP: usize = 4;
+const TRANSFERS: usize = 5;
+const LAST_TIMESTAMP: usize = 6;
+const MIN_VALUE: usize = 7;
+const MAX_VALUE: usize = 8;
+
+struct FieldNames {
+    names: [Vec<String>; FIELDS],
+}
+
+impl FieldNames {
+    fn new(columns: &ColumnMapping) -> Self {
+        let names = |mapped: &[String], common: &[&str]| -> Vec<String> {
+            mapped
+                .iter()
+                .map(|name| name.trim().to_lowercase())
+                .chain(common.iter().map(|name| name.to_string()))
+                .collect()
+        };
+        FieldNames {
+            names: [
+                names(&columns.from, &["source", "src", "from"]),
+                names(&columns.to, &["target", "dst", "to"]),
+                names(&columns.token, &["token"]),
+                names(&columns.value, &["value", "weight", "amount"]),
+                names(&columns.timestamp, &["timestamp", "first_timestamp", "time"]),
+                names(&[], &["transfers", "count"]),
+                names(&[], &["last_timestamp"]),
+                names(&[], &["min_value"]),
+                names(&[], &["max_value"]),
+            ],
+        }
+    }
+
+    /**************************************************************
+    *
+    *   The position of every field in a header, None when missing
+    *
+    ***************************************************************/
+
+    fn resolve(&self, header: &[String]) -> [Option<usize>; FIELDS] {
+        let header: Vec<String> = header.iter().map(|name| name.trim().trim_start_matches('\u{feff}').to_lowercase()).collect();
+        std::array::from_fn(|field| {
+            self.names[field]
+                .iter()
+                .find_map(|name| header.iter().position(|column| column == name))
+        })
+    }
+}
+
+fn parse_flow(values: [Option<&str>; FIELDS]) -> Result<Flow, String> {
+    let present = |field: usize| values[field].map(str::trim).filter(|value| !value.is_empty());
+    let from = present(FROM).ok_or("missing source address")?;
+    let to = present(TO).ok_or("missing target address")?;
+    let value = present(VALUE).map(parse_value).transpose()?.unwrap_or(1.0);
+    let count = match present(TRANSFERS) {
+        Some(count) => count.parse::<usize>().ok().filter(|&count| count > 0).ok_or(format!("invalid transfers '{}'", count))?,
+        None => 1,
+    };
+    let first_timestamp = present(TIMESTAMP).map(parse_timestamp).transpose()?;
+    let last_timestamp = present(LAST_TIMESTAMP).map(parse_timestamp).transpose()?;
+    let mean = value / count as f64;
+    let min_value = present(MIN_VALUE).map(parse_value).transpose()?.unwrap_or(mean);
+    let max_value = present(MAX_VALUE).map(parse_value).transpose()?.unwrap_or(mean);
+    Ok(Flow {
+        from: from.to_string(),
+        to: to.to_string(),
+        token: present(TOKEN).unwrap_or("unknown").to_string(),
+        flow: TokenFlow {
+            count,
+            value,
+            min_value,
+            max_value,
+            first_timestamp: first_timestamp.unwrap_or(0),
+            last_timestamp: last_timestamp.or(first_timestamp).unwrap_or(0),
+        },
+        timed: first_timestamp.is_some(),
+    })
+}
+
+fn flow_at(columns: &[Option<usize>; FIELDS], fields: &[&str]) -> Result<Flow, String> {
+    parse_flow(columns.map(|column| column.and_then(|index| fields.get(index).copied())))
+}
+
+/**************************************************************
+*
+*   The transfer csv of the project read by stream_transfers, the
+*   only source with sampling, row validation and USD values
+*
+***************************************************************/
+
+pub struct TransferCsvLoader {
+    pub path: String,
+}
+
+fn transfer_flow(from: &str, to: &str, transaction: &Transaction, value: f64) -> Flow {
+    Flow {
+        from: from.to_string(),
+        to: to.to_string(),
+        token: transaction.unit.clone(),
+        flow: TokenFlow::new(value, transaction.timestamp),
+        timed: true,
+    }
+}
+
+impl GraphLoader for TransferCsvLoader {
+    fn read_flows(
+        &self,
+        options: &LoadOptions,
+        visit: &mut dyn FnMut(usize, Result<Flow, String>) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        data_loader::stream_transfers(&self.path, options, |row, from, to, transaction, value| {
+            visit(row, Ok(transfer_flow(from, to, transaction, value)))
+        })?;
+        Ok(())
+    }
+
+    fn load_graph(&self, options: &LoadOptions) -> Result<(TransactionGraph, LoadSummary), Box<dyn std::error::Error>> {
+        // The values are already normalised and the rows checked by stream_transfers
+        let mut builder = GraphBuilder::new();
+        let summary = data_loader::stream_transfers(&self.path, options, |_, from, to, transaction, value| {
+            let flow = transfer_flow(from, to, transaction, value);
+            builder.add_token_flow(&flow.from, &flow.to, &flow.token, &flow.flow);
+            Ok(())
+        })?;
+        Ok((name_tokens(builder.build(), options), summary))
+    }
+
+    fn load_periods(&self, spec: &PeriodSpec, options: &LoadOptions) -> Result<(PeriodGraphs, LoadSummary), Box<dyn std::error::Error>> {
+        data_loader::load_csv_with_summary(&self.path, spec, options)
+    }
+}
+
+/**************************************************************
+*
+*   A plain edge list, comma, tab or space separated and possibly
+*   compressed, read as csv so quoted fields may hold the delimiter,
+*   lines starting with '#' or '%' are comments. With a header the
+*   fields are found by name, without one the columns are source,
+*   target, value, token and timestamp, the trailing ones optional
+*
+***************************************************************/
+
+pub struct EdgeListLoader {
+    pub path: String,
+}
+
+/**************************************************************
+*
+*   The delimiter of an edge list, found on its first data line
+*   within the sample, a space when it has no comma or tab
+*
+***************************************************************/
+
+fn edge_list_delimiter(sample: &[u8]) -> u8 {
+    let line = sample
+        .split(|&byte| byte == b'\n')
+        .find(|line| !line.trim_ascii().is_empty() && !line.starts_with(b"#") && !line.starts_with(b"%"))
+        .unwrap_or_default();
+    if line.contains(&b',') {
+        b','
+    } else if line.contains(&b'\t') {
+        b'\t'
+    } else {
+        b' '
+    }
+}
+
+impl GraphLoader for EdgeListLoader {
+    fn read_flows(
+        &self,
+        options: &LoadOptions,
+        visit: &mut dyn FnMut(usize, Result<Flow, String>) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let names = FieldNames::new(&options.columns);
+        let mut input = BufReader::with_capacity(1 << 16, open_input(&self.path)?.reader);
+        let delimiter = edge_list_delimiter(input.fill_buf()?);
+        // Quoted fields may hold the delimiter, runs of spaces separate one field
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .comment(Some(b'#'))
+            .trim(Trim::All)
+            .from_reader(input);
+        let mut record = StringRecord::new();
+        let mut columns = None;
+        let mut row = 0;
+        while rdr.read_record(&mut record)? {
+            let fields: Vec<&str> = record.iter().filter(|field| delimiter != b' ' || !field.is_empty()).collect();
+            if fields.iter().all(|field| field.is_empty()) || fields[0].starts_with('%') {
+                continue;
+            }
+            let columns = match columns {
+                Some(columns) => columns,
+                None => {
+                    let header: Vec<String> = fields.iter().map(|field| field.to_string()).collect();
+                    let named = names.resolve(&header);
+                    if named[FROM].is_some() && named[TO].is_some() {
+                        columns = Some(named);
+                        continue;
+                    }
+                    let positional = [Some(0), Some(1), Some(3), Some(2), Some(4), None, None, None, None];
+                    *columns.insert(positional)
+                }
+            };
+            visit(row, flow_at(&columns, &fields))?;
+            row += 1;
+        }
+        Ok(())
+    }
+}
+
+/**************************************************************
+*
+*   JSON lines, one object per line with the fields as string or
+*   number members, possibly compressed
+*
+***************************************************************/
+
+pub struct JsonLinesLoader {
+    pub path: String,
+}
+
+impl GraphLoader for JsonLinesLoader {
+    fn read_flows(
+        &self,
+        options: &LoadOptions,
+        visit: &mut dyn FnMut(usize, Result<Flow, String>) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let names = FieldNames::new(&options.columns);
+        let mut row = 0;
+        for line in BufReader::new(open_input(&self.path)?.reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let flow = match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&line) {
+                Ok(object) => {
+                    let header: Vec<String> = object.keys().cloned().collect();
+                    let fields: Vec<String> = object
+                        .values()
+                        .map(|value| match value {
+                            serde_json::Value::String(text) => text.clone(),
+                            serde_json::Value::Null => String::new(),
+                            other => other.to_string(),
+                        })
+                        .collect();
+                    let fields: Vec<&str> = fields.iter().map(String::as_str).collect();
+                    flow_at(&names.resolve(&header), &fields)
+                }
+                Err(e) => Err(format!("invalid JSON object: {}", e)),
+            };
+            visit(row, flow)?;
+            row += 1;
+        }
+        Ok(())
+    }
+}
+
+/**************************************************************
+*
+*   GraphML, the node label (a data key named label) is the
+*   address, or the node id when there is none. The fields are the
+*   attr.name of the edge data keys, so the GraphML written by
+*   export is read back. The edges are resolved once the whole file
+*   is read, so nodes may be listed before or after their edges.
+*   Addresses without an edge are left out
+*
+***************************************************************/
+
+pub struct GraphMlLoader {
+    pub path: String,
+}
+
+fn attribute(element: &BytesStart, name: &[u8]) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        if attribute.key.as_ref() == name {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+fn edge_flow(
+    names: &FieldNames,
+    labels: &HashMap<String, String>,
+    (source, target, mut values): (String, String, HashMap<String, String>),
+) -> Result<Flow, String> {
+    let label = |id: String| labels.get(&id).cloned().unwrap_or(id);
+    values.insert("source".to_string(), label(source));
+    values.insert("target".to_string(), label(target));
+    let (header, fields): (Vec<String>, Vec<&str>) = values.iter().map(|(name, value)| (name.clone(), value.as_str())).unzip();
+    flow_at(&names.resolve(&header), &fields)
+}
+
+impl GraphLoader for GraphMlLoader {
+    fn read_flows(
+        &self,
+        options: &LoadOptions,
+        visit: &mut dyn FnMut(usize, Result<Flow, String>) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let names = FieldNames::new(&options.columns);
+        let mut reader = Reader::from_reader(BufReader::new(open_input(&self.path)?.reader));
+        let mut buffer = Vec::new();
+        let mut keys: HashMap<String, String> = HashMap::new();
+        let mut labels: HashMap<String, String> = HashMap::new();
+        let mut node: Option<String> = None;
+        let mut edge: Option<(String, String, HashMap<String, String>)> = None;
+        let mut data: Option<(String, String)> = None;
+        // The labels of nodes listed after their edges are only known at the end
+        let mut edges = Vec::new();
+
+        loop {
+            let event = reader.read_event_into(&mut buffer)?;
+            let empty = matches!(event, Event::Empty(_));
+            match event {
+                Event::Start(element) | Event::Empty(element) => match element.local_name().as_ref() {
+                    b"key" => {
+                        if let (Some(id), Some(name)) = (attribute(&element, b"id")?, attribute(&element, b"attr.name")?) {
+                            keys.insert(id, name);
+                        }
+                    }
+                    b"node" if !empty => node = attribute(&element, b"id")?,
+                    b"edge" => {
+                        let source = attribute(&element, b"source")?.ok_or("edge without source")?;
+                        let target = attribute(&element, b"target")?.ok_or("edge without target")?;
+                        if empty {
+                            edges.push((source, target, HashMap::new()));
+                        } else {
+                            edge = Some((source, target, HashMap::new()));
+                        }
+                    }
+                    b"data" if !empty => {
+                        let key = attribute(&element, b"key")?.unwrap_or_default();
+                        data = Some((keys.get(&key).cloned().unwrap_or(key), String::new()));
+                    }
+                    _ => {}
+                },
+                Event::Text(text) => {
+                    if let Some((_, value)) = data.as_mut() {
+                        value.push_str(&text.unescape()?);
+                    }
+                }
+                Event::End(element) => match element.local_name().as_ref() {
+                    b"data" => match (data.take(), &node, edge.as_mut()) {
+                        (Some((name, value)), _, Some((_, _, values))) => {
+                            values.insert(name.to_lowercase(), value);
+                        }
+                        (Some((name, value)), Some(id), None) if name.eq_ignore_ascii_case("label") => {
+                            labels.insert(id.clone(), value);
+                        }
+                        _ => {}
+                    },
+                    b"node" => node = None,
+                    b"edge" => {
+                        if let Some(edge) = edge.take() {
+                            edges.push(edge);
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+            buffer.clear();
+        }
+        for (row, edge) in edges.into_iter().enumerate() {
+            visit(row, edge_flow(&names, &labels, edge))?;
+        }
+        Ok(())
+    }
+}
+
+/**************************************************************
+*
+*   Parquet as written by Spark, the fields are top level columns
+*   of any primitive type, timestamp columns are converted to
+*   epoch seconds
+*
+***************************************************************/
+
+#[cfg(feature = "parquet")]
+pub struct ParquetLoader {
+    pub path: String,
+}
+
+#[cfg(feature = "parquet")]
+fn parquet_text(field: &parquet::record::Field) -> Option<String> {
+    use parquet::record::Field;
+    match field {
+        Field::Null => None,
+        Field::Str(text) => Some(text.clone()),
+        Field::Bytes(bytes) => bytes.as_utf8().ok().map(str::to_string),
+        Field::TimestampMillis(millis) => Some(millis.div_euclid(1000).to_string()),
+        Field::TimestampMicros(micros) => Some(micros.div_euclid(1_000_000).to_string()),
+        Field::Date(days) => Some((*days as i64 * 86400).to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl GraphLoader for ParquetLoader {
+    fn read_flows(
+        &self,
+        options: &LoadOptions,
+        visit: &mut dyn FnMut(usize, Result<Flow, String>) -> Result<(), Box<dyn std::error::Error>>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        use std::fs::File;
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let reader = SerializedFileReader::new(File::open(&self.path)?)?;
+        let header: Vec<String> = reader
+            .metadata()
+            .file_metadata()
+            .schema()
+            .get_fields()
+            .iter()
+            .map(|field| field.name().to_string())
+            .collect();
+        let columns = FieldNames::new(&options.columns).resolve(&header);
+        for (row, record) in reader.get_row_iter(None)?.enumerate() {
+            let fields: Vec<Option<String>> = record?.get_column_iter().map(|(_, field)| parquet_text(field)).collect();
+            let fields: Vec<&str> = fields.iter().map(|field| field.as_deref().unwrap_or("")).collect();
+            visit(row, flow_at(&columns, &fields))?;
+        }
+        Ok(())
+    }
+}
+
+/**************************************************************
+*
+*   The file formats a graph is loaded from:
+*   Transfers: the transfer csv of the project
+*   Edges: a plain edge list
+*   GraphMl, JsonLines, Parquet
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    Transfers,
+    Edges,
+    GraphMl,
+    JsonLines,
+    Parquet,
+}
+
+impl InputFormat {
+    /**************************************************************
+    *
+    *   Guess the format from the file name, ignoring a .gz or .zst
+    *   compression suffix, every unknown name is a transfer csv
+    *
+    ***************************************************************/
+
+    pub fn from_path(path: &str) -> Self {
+        let name = path.to_lowercase();
+        let name = name.trim_end_matches(".gz").trim_end_matches(".zst");
+        let extension = Path::new(name).extension().and_then(|extension| extension.to_str()).unwrap_or("");
+        match extension {
+            "graphml" => InputFormat::GraphMl,
+            "jsonl" | "ndjson" => InputFormat::JsonLines,
+            "parquet" => InputFormat::Parquet,
+            "tsv" | "txt" | "edges" | "edgelist" => InputFormat::Edges,
+            _ if name.ends_with(".edges.csv") => InputFormat::Edges,
+            _ => InputFormat::Transfers,
+        }
+    }
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "transfers" | "csv" => Ok(InputFormat::Transfers),
+            "edges" => Ok(InputFormat::Edges),
+            "graphml" => Ok(InputFormat::GraphMl),
+            "jsonl" => Ok(InputFormat::JsonLines),
+            "parquet" => Ok(InputFormat::Parquet),
+            _ => Err(format!("unknown input format '{}', expected transfers, edges, graphml, jsonl or parquet", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The loader of a file in the given format
+*
+***************************************************************/
+
+pub fn loader_for(format: InputFormat, path: &str) -> Result<Box<dyn GraphLoader>, String> {
+    let path = path.to_string();
+    match format {
+        InputFormat::Transfers => Ok(Box::new(TransferCsvLoader { path })),
+        InputFormat::Edges => Ok(Box::new(EdgeListLoader { path })),
+        InputFormat::GraphMl => Ok(Box::new(GraphMlLoader { path })),
+        InputFormat::JsonLines => Ok(Box::new(JsonLinesLoader { path })),
+        #[cfg(feature = "parquet")]
+        InputFormat::Parquet => Ok(Box::new(ParquetLoader { path })),
+        #[cfg(not(feature = "parquet"))]
+        InputFormat::Parquet => Err("parquet input needs the parquet feature".to_string()),
+    }
+}
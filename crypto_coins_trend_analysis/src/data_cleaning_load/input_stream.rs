@@ -0,0 +1,136 @@
+use std::cell::Cell;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::rc::Rc;
+use std::time::Instant;
+use flate2::read::MultiGzDecoder;
+
+//Compression, InputStream, open_input, Progress
+
+/**************************************************************
+*
+*   The compression of an input file, detected from the first
+*   bytes of the file rather than the extension:
+*   None: plain text
+*   Gzip: starts with the gzip magic 1f 8b
+*   Zstd: starts with the zstd frame magic 28 b5 2f fd
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn detect(header: &[u8]) -> Self {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Compression::Zstd
+        } else {
+            Compression::None
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Wraps the file and counts the (compressed) bytes read from it,
+*   the counter is shared with Progress while the reader itself is
+*   owned by the csv reader
+*
+***************************************************************/
+
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.bytes_read.set(self.bytes_read.get() + read as u64);
+        Ok(read)
+    }
+}
+
+/**************************************************************
+*
+*   An opened input file, decompressed on the fly:
+*   reader: the decompressed content
+*   compression: the detected compression
+*   bytes_read, total_bytes: position in the file on disk, used to
+*   report progress
+*
+***************************************************************/
+
+pub struct InputStream {
+    pub reader: Box<dyn Read>,
+    pub compression: Compression,
+    pub bytes_read: Rc<Cell<u64>>,
+    pub total_bytes: u64,
+}
+
+/**************************************************************
+*
+*   Open a plain, gzip or zstd compressed input file for streaming,
+*   nothing is read into memory beyond the decoder buffers
+*
+***************************************************************/
+
+pub fn open_input(path: &str) -> Result<InputStream, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
+    let total_bytes = file.metadata()?.len();
+    let bytes_read = Rc::new(Cell::new(0));
+    let mut buffered = BufReader::new(CountingReader { inner: file, bytes_read: Rc::clone(&bytes_read) });
+
+    let compression = Compression::detect(buffered.fill_buf()?);
+    let reader: Box<dyn Read> = match compression {
+        Compression::None => Box::new(buffered),
+        Compression::Gzip => Box::new(MultiGzDecoder::new(buffered)),
+        Compression::Zstd => Box::new(zstd::Decoder::with_buffer(buffered)?),
+    };
+    Ok(InputStream { reader, compression, bytes_read, total_bytes })
+}
+
+/**************************************************************
+*
+*   Reports the progress of a long load to stderr, the share of
+*   the file read so far and the rows per second
+*
+***************************************************************/
+
+pub struct Progress {
+    enabled: bool,
+    started: Instant,
+    bytes_read: Rc<Cell<u64>>,
+    total_bytes: u64,
+}
+
+impl Progress {
+    pub fn new(input: &InputStream, enabled: bool) -> Self {
+        Progress {
+            enabled,
+            started: Instant::now(),
+            bytes_read: Rc::clone(&input.bytes_read),
+            total_bytes: input.total_bytes,
+        }
+    }
+
+    pub fn report(&self, rows: usize) {
+        if !self.enabled {
+            return;
+        }
+        let seconds = self.started.elapsed().as_secs_f64();
+        let percent = if self.total_bytes == 0 {
+            100.0
+        } else {
+            100.0 * self.bytes_read.get() as f64 / self.total_bytes as f64
+        };
+        let rate = if seconds > 0.0 { rows as f64 / seconds } else { 0.0 };
+        eprintln!("  loaded {} rows ({:.1}% of input, {:.0} rows/s)", rows, percent.min(100.0), rate);
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+use std::path::Path;
+use serde::Deserialize;
+use crate::data_cleaning_load::column_mapping::format_timestamp;
+
+//Period, PeriodSpec, WindowSpec, parse_duration
+
+/**************************************************************
+*
+*   The datastructure that represents one named analysis period,
+*   a transaction belongs to the period if its timestamp t
+*   satisfies start <= t < end (unix epoch seconds)
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Period {
+    pub name: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Period {
+    pub fn new(name: &str, start: u64, end: u64) -> Self {
+        Period {
+            name: name.to_string(),
+            start,
+            end,
+        }
+    }
+
+    pub fn contains(&self, timestamp: u64) -> bool {
+        timestamp >= self.start && timestamp < self.end
+    }
+}
+
+/**************************************************************
+*
+*   The period specification consumed by the loader, an ordered
+*   list of any number of periods. It could be loaded from a TOML
+*   or JSON file of the form:
+*
+*   [[periods]]
+*   name = "before"
+*   start = 1651104000
+*   end = 1651708800
+*
+*   or built from command line arguments "name:start:end"
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PeriodSpec {
+    pub periods: Vec<Period>,
+}
+
+impl PeriodSpec {
+    pub fn new(periods: Vec<Period>) -> Result<Self, Box<dyn std::error::Error>> {
+        let spec = PeriodSpec { periods };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /**************************************************************
+    *
+    *   The default periods of this project, one week before, during
+    *   and after the LUNA crash in May 2022
+    *
+    ***************************************************************/
+
+    pub fn luna_crash() -> Self {
+        PeriodSpec {
+            periods: vec![
+                Period::new("before", 1651104000, 1651708800),
+                Period::new("during", 1651708800, 1652400000),
+                Period::new("after", 1652400000, 1652918400),
+            ],
+        }
+    }
+
+    /**************************************************************
+    *
+    *   Load the period specification from a file, the format is
+    *   chosen by the extension (.toml or .json)
+    *
+    ***************************************************************/
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let spec: PeriodSpec = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content)?,
+            Some("json") => serde_json::from_str(&content)?,
+            _ => return Err(format!("unsupported period file '{}', expected .toml or .json", path).into()),
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /**************************************************************
+    *
+    *   Build the period specification from arguments of the form
+    *   "name:start:end", one argument for each period
+    *
+    ***************************************************************/
+
+    pub fn from_args(args: &[String]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut periods = Vec::new();
+        for arg in args {
+            let parts: Vec<&str> = arg.split(':').collect();
+            if parts.len() != 3 {
+                return Err(format!("invalid period '{}', expected name:start:end", arg).into());
+            }
+            periods.push(Period::new(parts[0], parts[1].parse::<u64>()?, parts[2].parse::<u64>()?));
+        }
+        PeriodSpec::new(periods)
+    }
+
+    /**************************************************************
+    *
+    *   The time range covered by the periods, from the earliest
+    *   start to the latest end
+    *
+    ***************************************************************/
+
+    pub fn range(&self) -> (u64, u64) {
+        let start = self.periods.iter().map(|period| period.start).min().unwrap_or(0);
+        let end = self.periods.iter().map(|period| period.end).max().unwrap_or(0);
+        (start, end)
+    }
+
+    /**************************************************************
+    *
+    *   Build a series of windows over start..end, one window of the
+    *   given width every step seconds, only windows that fit entirely
+    *   in the range are created so all of them are comparable.
+    *   step < width gives sliding (overlapping) windows, step == width
+    *   tumbling ones. Every window is named by its UTC start time.
+    *   A window that would end past u64::MAX does not fit
+    *
+    ***************************************************************/
+
+    pub fn windows(start: u64, end: u64, windows: &WindowSpec) -> Result<Self, Box<dyn std::error::Error>> {
+        if windows.width == 0 || windows.step == 0 {
+            return Err(format!("window width and step must be positive, got {}:{}", windows.width, windows.step).into());
+        }
+        let mut periods = Vec::new();
+        let mut window_start = start;
+        while let Some(window_end) = window_start.checked_add(windows.width).filter(|&window_end| window_end <= end) {
+            periods.push(Period::new(&format_timestamp(window_start), window_start, window_end));
+            match window_start.checked_add(windows.step) {
+                Some(next_start) => window_start = next_start,
+                None => break,
+            }
+        }
+        if periods.is_empty() {
+            return Err(format!("no window of {} seconds fits between {} and {}", windows.width, start, end).into());
+        }
+        PeriodSpec::new(periods)
+    }
+
+    fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.periods.is_empty() {
+            return Err("period specification contains no periods".into());
+        }
+        let mut names = HashSet::new();
+        for period in &self.periods {
+            if period.start >= period.end {
+                return Err(format!("period '{}' must start before it ends", period.name).into());
+            }
+            if !names.insert(period.name.as_str()) {
+                return Err(format!("period '{}' is defined more than once", period.name).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/**************************************************************
+*
+*   The width and step of a window series in seconds, parsed from
+*   "<width>:<step>" such as "6h:1h" (sliding) or "1d:1d" (tumbling)
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WindowSpec {
+    pub width: u64,
+    pub step: u64,
+}
+
+impl std::str::FromStr for WindowSpec {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (width, step) = value
+            .split_once(':')
+            .ok_or_else(|| format!("invalid windows '{}', expected <width>:<step> such as 6h:1h", value))?;
+        let (width, step) = (parse_duration(width)?, parse_duration(step)?);
+        if width == 0 || step == 0 {
+            return Err(format!("invalid windows '{}', width and step must be positive", value));
+        }
+        Ok(WindowSpec { width, step })
+    }
+}
+
+/**************************************************************
+*
+*   Parse a duration in seconds, a number with an optional unit
+*   s, m, h, d or w, e.g. "90m" or "6h"
+*
+***************************************************************/
+
+pub fn parse_duration(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse().map_err(|_| format!("invalid duration '{}'", value))?;
+    let unit_seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        "w" => 604800,
+        _ => return Err(format!("invalid duration unit in '{}', expected s, m, h, d or w", value)),
+    };
+    number
+        .checked_mul(unit_seconds)
+        .ok_or_else(|| format!("duration '{}' is too long", value))
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use csv::ReaderBuilder;
+use crate::data_cleaning_load::column_mapping::{parse_timestamp, parse_value};
+use crate::data_cleaning_load::token_registry::TokenRegistry;
+
+//ValueBasis, PriceTable
+
+/**************************************************************
+*
+*   Selects the unit of the values in the graphs:
+*   Token: the (normalised) token amount, the default
+*   Usd: the USD value of the transfer at its timestamp
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValueBasis {
+    #[default]
+    Token,
+    Usd,
+}
+
+impl std::str::FromStr for ValueBasis {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "token" => Ok(ValueBasis::Token),
+            "usd" => Ok(ValueBasis::Usd),
+            _ => Err(format!("unknown value basis '{}', expected token or usd", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The historical USD prices of the tokens, loaded from a local CSV
+*   with the columns token, timestamp and price_usd:
+*
+*   token,timestamp,price_usd
+*   WLUNA,2022-05-09 00:00:00,30.12
+*   0xa693b19d2931d498c5b318df961919bb4aee87a5,1652054400,0.61
+*
+*   the token is a contract address or a registry symbol and the
+*   timestamp epoch seconds or a UTC date time. The prices of every
+*   token are kept sorted by time for the nearest-prior lookup
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PriceTable {
+    prices: HashMap<String, Vec<(u64, f64)>>,
+}
+
+impl PriceTable {
+    pub fn new() -> Self {
+        PriceTable::default()
+    }
+
+    pub fn insert(&mut self, token: &str, timestamp: u64, price: f64) {
+        let series = self.prices.entry(token.to_lowercase()).or_default();
+        let position = series.partition_point(|(time, _)| *time <= timestamp);
+        series.insert(position, (timestamp, price));
+    }
+
+    pub fn from_csv(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut rdr = ReaderBuilder::new().from_path(path)?;
+        let header = rdr.headers()?.clone();
+        let column = |names: &[&str]| {
+            header
+                .iter()
+                .position(|column| names.contains(&column.trim().to_lowercase().as_str()))
+                .ok_or_else(|| format!("price file '{}' has no {} column", path, names[0]))
+        };
+        let (token, timestamp, price) = (
+            column(&["token", "symbol", "contract_address"])?,
+            column(&["timestamp", "time_stamp", "date"])?,
+            column(&["price_usd", "usd_price", "price"])?,
+        );
+
+        let mut table = PriceTable::new();
+        for (row, result) in rdr.records().enumerate() {
+            let record = result?;
+            let at = |e: String| format!("price file '{}' row {}: {}", path, row + 1, e);
+            table.insert(
+                &record[token],
+                parse_timestamp(&record[timestamp]).map_err(at)?,
+                parse_value(&record[price]).map_err(at)?,
+            );
+        }
+        Ok(table)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+
+    /**************************************************************
+    *
+    *   The price of the token in effect at the timestamp, the latest
+    *   price at or before it, None before the first price
+    *
+    ***************************************************************/
+
+    pub fn price_at(&self, token: &str, timestamp: u64) -> Option<f64> {
+        let series = self.prices.get(&token.to_lowercase())?;
+        let position = series.partition_point(|(time, _)| *time <= timestamp);
+        position.checked_sub(1).map(|index| series[index].1)
+    }
+
+    /**************************************************************
+    *
+    *   The USD value of an amount of the token (a contract address),
+    *   the prices are looked up by address and then by its registry
+    *   symbol. A USD-pegged token without any price in the table is
+    *   valued at its peg, so a table only needs the volatile tokens,
+    *   list a stablecoin to follow its depeg
+    *
+    ***************************************************************/
+
+    pub fn usd_value(&self, registry: &TokenRegistry, token: &str, amount: f64, timestamp: u64) -> Option<f64> {
+        let symbol = registry.symbol(token);
+        for key in [token, symbol] {
+            if self.prices.contains_key(&key.to_lowercase()) {
+                return self.price_at(key, timestamp).map(|price| amount * price);
+            }
+        }
+        match registry.get(token).and_then(|info| info.peg.as_deref()) {
+            Some("USD") => Some(amount),
+            _ => None,
+        }
+    }
+}
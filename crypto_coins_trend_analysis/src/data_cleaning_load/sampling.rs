@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use crate::graph::transaction_graph::{GraphBuilder, TransactionGraph};
+
+//Sampling, PeriodSampler
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/**************************************************************
+*
+*   Selects which transfers of a period are loaded into its graph:
+*   All: every transfer, no limit
+*   First(n): the first n transfers in file order, the behaviour
+*   of the original loader which capped every period at 10000
+*   Reservoir(n): a uniform random sample of n transfers
+*   StratifiedByDay(n): a uniform random sample of n transfers
+*   from every day (UTC) of the period, so busy days do not crowd
+*   out quiet ones
+*   The random modes are drawn from a seed and are reproducible
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Sampling {
+    #[default]
+    All,
+    First(usize),
+    Reservoir(usize),
+    StratifiedByDay(usize),
+}
+
+/**************************************************************
+*
+*   Parse the sampling mode from "all", "first:<n>",
+*   "reservoir:<n>" or "daily:<n>"
+*
+***************************************************************/
+
+impl std::str::FromStr for Sampling {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "all" {
+            return Ok(Sampling::All);
+        }
+        let expected = "expected all, first:<n>, reservoir:<n> or daily:<n>";
+        let (mode, size) = value
+            .split_once(':')
+            .ok_or_else(|| format!("unknown sampling '{}', {}", value, expected))?;
+        let size: usize = size
+            .parse()
+            .map_err(|_| format!("invalid sample size in '{}', {}", value, expected))?;
+        match mode {
+            "first" => Ok(Sampling::First(size)),
+            "reservoir" => Ok(Sampling::Reservoir(size)),
+            "daily" => Ok(Sampling::StratifiedByDay(size)),
+            _ => Err(format!("unknown sampling '{}', {}", value, expected)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   One transfer kept by a random sample until the period is built,
+*   row is its position in the file so the sampled transfers are
+*   added in file order
+*
+***************************************************************/
+
+#[derive(Debug, Clone)]
+struct SampledTransfer {
+    row: usize,
+    from_address: String,
+    to_address: String,
+    unit: String,
+    value: f64,
+    timestamp: u64,
+}
+
+/**************************************************************
+*
+*   A reservoir of at most capacity transfers (Algorithm R), every
+*   transfer offered so far has the same chance to be in it
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default)]
+struct Reservoir {
+    seen: usize,
+    transfers: Vec<SampledTransfer>,
+}
+
+impl Reservoir {
+    fn offer(&mut self, transfer: SampledTransfer, capacity: usize, rng: &mut StdRng) {
+        self.seen += 1;
+        if self.transfers.len() < capacity {
+            self.transfers.push(transfer);
+            return;
+        }
+        let slot = rng.gen_range(0..self.seen);
+        if slot < capacity {
+            self.transfers[slot] = transfer;
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Collects the transfers of one period according to the sampling
+*   mode. All and First add straight into the GraphBuilder, the
+*   random modes hold the sampled transfers until build()
+*
+***************************************************************/
+
+#[derive(Debug, Clone)]
+pub struct PeriodSampler {
+    sampling: Sampling,
+    rng: StdRng,
+    builder: GraphBuilder,
+    offered: usize,
+    kept: usize,
+    reservoir: Reservoir,
+    days: BTreeMap<u64, Reservoir>,
+}
+
+impl PeriodSampler {
+    pub fn new(sampling: Sampling, seed: u64) -> Self {
+        PeriodSampler {
+            sampling,
+            rng: StdRng::seed_from_u64(seed),
+            builder: GraphBuilder::new(),
+            offered: 0,
+            kept: 0,
+            reservoir: Reservoir::default(),
+            days: BTreeMap::new(),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   Offer the transfer on the given row of the file to the period
+    *
+    ***************************************************************/
+
+    pub fn offer(&mut self, row: usize, from_address: &str, to_address: &str, unit: &str, value: f64, timestamp: u64) {
+        self.offered += 1;
+        let transfer = || SampledTransfer {
+            row,
+            from_address: from_address.to_string(),
+            to_address: to_address.to_string(),
+            unit: unit.to_string(),
+            value,
+            timestamp,
+        };
+        match self.sampling {
+            Sampling::All => {
+                self.builder.add_transfer(from_address, to_address, unit, value, timestamp);
+                self.kept += 1;
+            }
+            Sampling::First(limit) => {
+                if self.kept < limit {
+                    self.builder.add_transfer(from_address, to_address, unit, value, timestamp);
+                    self.kept += 1;
+                }
+            }
+            Sampling::Reservoir(capacity) => self.reservoir.offer(transfer(), capacity, &mut self.rng),
+            Sampling::StratifiedByDay(capacity) => self
+                .days
+                .entry(timestamp / SECONDS_PER_DAY)
+                .or_default()
+                .offer(transfer(), capacity, &mut self.rng),
+        }
+    }
+
+    /**************************************************************
+    *
+    *   The number of transfers offered to and kept by the period,
+    *   a random sample is only final once every row is offered
+    *
+    ***************************************************************/
+
+    pub fn offered(&self) -> usize {
+        self.offered
+    }
+
+    pub fn kept(&self) -> usize {
+        match self.sampling {
+            Sampling::All | Sampling::First(_) => self.kept,
+            Sampling::Reservoir(_) => self.reservoir.transfers.len(),
+            Sampling::StratifiedByDay(_) => self.days.values().map(|day| day.transfers.len()).sum(),
+        }
+    }
+
+    pub fn build(self) -> TransactionGraph {
+        let mut builder = self.builder;
+        let mut sampled: Vec<SampledTransfer> = self.reservoir.transfers;
+        for day in self.days.into_values() {
+            sampled.extend(day.transfers);
+        }
+        sampled.sort_by_key(|transfer| transfer.row);
+        for transfer in &sampled {
+            builder.add_transfer(
+                &transfer.from_address,
+                &transfer.to_address,
+                &transfer.unit,
+                transfer.value,
+                transfer.timestamp,
+            );
+        }
+        builder.build()
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::path::Path;
+use serde::Deserialize;
+
+//TokenInfo, TokenRegistry
+
+/**************************************************************
+*
+*   The metadata of one token contract:
+*   address: the contract address
+*   symbol: the ticker shown instead of the address, e.g. USDT
+*   decimals: the raw on-chain amount is value * 10^decimals
+*   peg: the currency the token is pegged to, e.g. USD, if any
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenInfo {
+    pub address: String,
+    pub symbol: String,
+    pub decimals: u32,
+    #[serde(default)]
+    pub peg: Option<String>,
+}
+
+impl TokenInfo {
+    pub fn new(address: &str, symbol: &str, decimals: u32, peg: Option<&str>) -> Self {
+        TokenInfo {
+            address: address.to_lowercase(),
+            symbol: symbol.to_string(),
+            decimals,
+            peg: peg.map(|peg| peg.to_string()),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The registry of known tokens keyed by lowercase contract address.
+*   It could be loaded from a TOML or JSON file of the form:
+*
+*   [[tokens]]
+*   address = "0xdac17f958d2ee523a2206206994597c13d831ec7"
+*   symbol = "USDT"
+*   decimals = 6
+*   peg = "USD"
+*
+*   the entries of a file are added to the built-in tokens and
+*   replace them when the address is the same
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenRegistry {
+    tokens: HashMap<String, TokenInfo>,
+}
+
+#[derive(Deserialize)]
+struct TokenFile {
+    tokens: Vec<TokenInfo>,
+}
+
+impl Default for TokenRegistry {
+    fn default() -> Self {
+        TokenRegistry::builtin()
+    }
+}
+
+impl TokenRegistry {
+    pub fn empty() -> Self {
+        TokenRegistry { tokens: HashMap::new() }
+    }
+
+    /**************************************************************
+    *
+    *   The stablecoins and WLUNA of the Chartalist dataset
+    *
+    ***************************************************************/
+
+    pub fn builtin() -> Self {
+        let mut registry = TokenRegistry::empty();
+        for token in [
+            TokenInfo::new("0xdac17f958d2ee523a2206206994597c13d831ec7", "USDT", 6, Some("USD")),
+            TokenInfo::new("0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "USDC", 6, Some("USD")),
+            TokenInfo::new("0x6b175474e89094c44da98b954eedeac495271d0f", "DAI", 18, Some("USD")),
+            TokenInfo::new("0xa693b19d2931d498c5b318df961919bb4aee87a5", "UST", 6, Some("USD")),
+            TokenInfo::new("0xd2877702675e6ceb975b4a1dff9fb7baf4c91ea9", "WLUNA", 18, None),
+            TokenInfo::new("0x8e870d67f660d95d5be530380d0ec0bd388289e1", "PAX", 18, Some("USD")),
+            TokenInfo::new("0x4fabb145d64652a948d72533023f6e7a623c7c53", "BUSD", 18, Some("USD")),
+        ] {
+            registry.insert(token);
+        }
+        registry
+    }
+
+    pub fn insert(&mut self, token: TokenInfo) {
+        let address = token.address.to_lowercase();
+        self.tokens.insert(address.clone(), TokenInfo { address, ..token });
+    }
+
+    /**************************************************************
+    *
+    *   Load the built-in registry extended by a file, the format is
+    *   chosen by the extension (.toml or .json)
+    *
+    ***************************************************************/
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        let file: TokenFile = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content)?,
+            Some("json") => serde_json::from_str(&content)?,
+            _ => return Err(format!("unsupported token file '{}', expected .toml or .json", path).into()),
+        };
+        let mut registry = TokenRegistry::builtin();
+        for token in file.tokens {
+            registry.insert(token);
+        }
+        Ok(registry)
+    }
+
+    pub fn get(&self, address: &str) -> Option<&TokenInfo> {
+        self.tokens.get(&address.to_lowercase())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /**************************************************************
+    *
+    *   The symbol of a token, unknown tokens keep their address
+    *
+    ***************************************************************/
+
+    pub fn symbol<'a>(&'a self, address: &'a str) -> &'a str {
+        self.get(address).map_or(address, |token| token.symbol.as_str())
+    }
+
+    /**************************************************************
+    *
+    *   Convert a raw on-chain amount into whole token units, the
+    *   amount of an unknown token is returned unchanged
+    *
+    ***************************************************************/
+
+    pub fn normalize(&self, address: &str, raw: f64) -> f64 {
+        match self.get(address) {
+            Some(token) => raw / 10f64.powi(token.decimals as i32),
+            None => raw,
+        }
+    }
+}
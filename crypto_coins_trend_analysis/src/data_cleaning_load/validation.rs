@@ -0,0 +1,273 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::time::{SystemTime, UNIX_EPOCH};
+use csv::{StringRecord, Writer, WriterBuilder};
+use crate::data_cleaning_load::column_mapping::{parse_timestamp, parse_value, ColumnIndices};
+
+//ValidationMode, Validation, RejectReason, Rejection, CheckedTransfer, check_record,
+//check_transfer, is_valid_address, LoadSummary, QuarantineWriter
+
+/**************************************************************
+*
+*   Selects what the loader does with a bad row:
+*   Off: the legacy behaviour, only the timestamp and value are
+*   parsed and the first unparsable row aborts the load
+*   Skip: every row is validated and bad rows are counted and dropped
+*   Quarantine(path): as Skip, the bad rows are also written to a
+*   side CSV with the reason in an extra column
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ValidationMode {
+    #[default]
+    Off,
+    Skip,
+    Quarantine(String),
+}
+
+/**************************************************************
+*
+*   The validation of the rows of one load, a valid timestamp lies
+*   in min_timestamp..=max_timestamp, by default from the Ethereum
+*   genesis block to the time of loading
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Validation {
+    pub mode: ValidationMode,
+    pub min_timestamp: u64,
+    pub max_timestamp: u64,
+}
+
+impl Default for Validation {
+    fn default() -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(u64::MAX);
+        Validation { mode: ValidationMode::Off, min_timestamp: 1438269973, max_timestamp: now }
+    }
+}
+
+/**************************************************************
+*
+*   Why a row was rejected, the summary lists them in this order
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RejectReason {
+    MalformedRow,
+    InvalidAddress,
+    InvalidValue,
+    InvalidTimestamp,
+    TimestampOutOfRange,
+    SelfTransfer,
+}
+
+impl std::fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = match self {
+            RejectReason::MalformedRow => "malformed_row",
+            RejectReason::InvalidAddress => "invalid_address",
+            RejectReason::InvalidValue => "invalid_value",
+            RejectReason::InvalidTimestamp => "invalid_timestamp",
+            RejectReason::TimestampOutOfRange => "timestamp_out_of_range",
+            RejectReason::SelfTransfer => "self_transfer",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rejection {
+    pub reason: RejectReason,
+    pub message: String,
+}
+
+impl Rejection {
+    fn new(reason: RejectReason, message: String) -> Self {
+        Rejection { reason, message }
+    }
+}
+
+/**************************************************************
+*
+*   The fields of one row that passed the checks, borrowed from
+*   the record
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckedTransfer<'a> {
+    pub from_address: &'a str,
+    pub to_address: &'a str,
+    pub token: &'a str,
+    pub value: f64,
+    pub timestamp: u64,
+}
+
+/**************************************************************
+*
+*   An Ethereum address, "0x" followed by 40 hex digits
+*
+***************************************************************/
+
+pub fn is_valid_address(address: &str) -> bool {
+    address.len() == 42
+        && (address.starts_with("0x") || address.starts_with("0X"))
+        && address[2..].bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/**************************************************************
+*
+*   Parse and check one record, with ValidationMode::Off only the
+*   parsing can fail, otherwise the addresses, value, timestamp
+*   range and self-transfers are checked too
+*
+***************************************************************/
+
+pub fn check_record<'a>(
+    record: &'a StringRecord,
+    columns: &ColumnIndices,
+    validation: &Validation,
+) -> Result<CheckedTransfer<'a>, Rejection> {
+    let field = |index: usize| {
+        record.get(index).ok_or_else(|| {
+            Rejection::new(RejectReason::MalformedRow, format!("row has {} fields, expected at least {}", record.len(), index + 1))
+        })
+    };
+    let from_address = field(columns.from)?;
+    let to_address = field(columns.to)?;
+    let token = field(columns.token)?;
+    let timestamp = parse_timestamp(field(columns.timestamp)?)
+        .map_err(|e| Rejection::new(RejectReason::InvalidTimestamp, e))?;
+    let value = parse_value(field(columns.value)?).map_err(|e| Rejection::new(RejectReason::InvalidValue, e))?;
+    let transfer = CheckedTransfer { from_address, to_address, token, value, timestamp };
+    if validation.mode == ValidationMode::Off {
+        return Ok(transfer);
+    }
+
+    check_transfer(from_address, to_address, &[value], &[timestamp], validation)?;
+    Ok(transfer)
+}
+
+/**************************************************************
+*
+*   The checks of a parsed transfer shared by every input format:
+*   valid addresses, finite non-negative values, timestamps in the
+*   range of the validation and no self-transfer. An aggregated
+*   record passes all its values and timestamps, a record without
+*   time none
+*
+***************************************************************/
+
+pub fn check_transfer(
+    from_address: &str,
+    to_address: &str,
+    values: &[f64],
+    timestamps: &[u64],
+    validation: &Validation,
+) -> Result<(), Rejection> {
+    for address in [from_address, to_address] {
+        if !is_valid_address(address) {
+            return Err(Rejection::new(RejectReason::InvalidAddress, format!("invalid address '{}'", address)));
+        }
+    }
+    for &value in values {
+        if !value.is_finite() || value < 0.0 {
+            return Err(Rejection::new(RejectReason::InvalidValue, format!("value {} is not a finite non-negative number", value)));
+        }
+    }
+    for &timestamp in timestamps {
+        if timestamp < validation.min_timestamp || timestamp > validation.max_timestamp {
+            return Err(Rejection::new(
+                RejectReason::TimestampOutOfRange,
+                format!("timestamp {} outside {}..={}", timestamp, validation.min_timestamp, validation.max_timestamp),
+            ));
+        }
+    }
+    if from_address.eq_ignore_ascii_case(to_address) {
+        return Err(Rejection::new(RejectReason::SelfTransfer, format!("self-transfer of '{}'", from_address)));
+    }
+    Ok(())
+}
+
+/**************************************************************
+*
+*   The summary of one load:
+*   rows_read: data rows in the file
+*   accepted: rows that passed the validation
+*   rejected: number of dropped rows for every reason
+*   unpriced: accepted rows left out of USD valued graphs because
+*   their token has no price at their timestamp
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadSummary {
+    pub rows_read: usize,
+    pub accepted: usize,
+    pub rejected: BTreeMap<RejectReason, usize>,
+    pub unpriced: usize,
+}
+
+impl LoadSummary {
+    pub fn reject(&mut self, reason: RejectReason) {
+        *self.rejected.entry(reason).or_default() += 1;
+    }
+
+    pub fn rejected_count(&self) -> usize {
+        self.rejected.values().sum()
+    }
+
+    pub fn display(&self) {
+        println!(
+            "Load summary: {} rows read, {} accepted, {} rejected",
+            self.rows_read,
+            self.accepted,
+            self.rejected_count()
+        );
+        for (reason, count) in &self.rejected {
+            println!("  {}: {}", reason, count);
+        }
+        if self.unpriced > 0 {
+            println!("  {} accepted rows without a USD price left out", self.unpriced);
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Writes the rejected rows to the quarantine CSV, the header of
+*   the input with the columns row and reason appended, malformed
+*   rows are written with the fields they have
+*
+***************************************************************/
+
+pub struct QuarantineWriter {
+    writer: Writer<File>,
+}
+
+impl QuarantineWriter {
+    pub fn create(path: &str, header: &StringRecord) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut writer = WriterBuilder::new().flexible(true).from_path(path)?;
+        let mut columns: Vec<&str> = header.iter().collect();
+        columns.extend(["row", "reason"]);
+        writer.write_record(&columns)?;
+        Ok(QuarantineWriter { writer })
+    }
+
+    pub fn write(&mut self, row: usize, record: &StringRecord, rejection: &Rejection) -> Result<(), Box<dyn std::error::Error>> {
+        let row = row.to_string();
+        let reason = format!("{}: {}", rejection.reason, rejection.message);
+        let mut fields: Vec<&str> = record.iter().collect();
+        fields.extend([row.as_str(), reason.as_str()]);
+        self.writer.write_record(&fields)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use memmap2::Mmap;
+use crate::data_cleaning_load::data_loader::PeriodGraphs;
+use crate::data_cleaning_load::period_config::{Period, PeriodSpec};
+use crate::graph::transaction_graph::{TransactionGraph, Edge, TokenFlow, NodeId, TokenId, EdgeId};
+
+//SNAPSHOT_MAGIC, SNAPSHOT_VERSION, SnapshotError, SourceStamp, Snapshot, save_snapshot, load_snapshot
+
+/**************************************************************
+*
+*   A snapshot is a little endian binary file of the built period
+*   graphs so a run can skip parsing the CSV:
+*
+*   header:  magic "TXGSNAP\0", format version u32,
+*            payload length u64, crc32 of the payload u32
+*   payload: source stamp (size u64, modified u64), settings,
+*            the periods (name, start u64, end u64), then every
+*            graph with its name, addresses, tokens with their
+*            symbol and the edges in EdgeId order with their
+*            per-token flows
+*
+*   strings are a u32 byte length followed by the UTF-8 bytes.
+*   SNAPSHOT_VERSION changes with every change of the layout
+*
+***************************************************************/
+
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"TXGSNAP\0";
+pub const SNAPSHOT_VERSION: u32 = 1;
+const HEADER_LEN: usize = 8 + 4 + 8 + 4;
+
+/**************************************************************
+*
+*   Why a snapshot could not be used, everything but Io means the
+*   file is stale or damaged and should be rebuilt from the CSV
+*
+***************************************************************/
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(std::io::Error),
+    NotASnapshot,
+    UnsupportedVersion(u32),
+    ChecksumMismatch { expected: u32, found: u32 },
+    Corrupt(String),
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SnapshotError::Io(e) => write!(f, "{}", e),
+            SnapshotError::NotASnapshot => write!(f, "not a graph snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "snapshot format version {} but version {} is supported", version, SNAPSHOT_VERSION)
+            }
+            SnapshotError::ChecksumMismatch { expected, found } => {
+                write!(f, "snapshot checksum {:08x} does not match its content {:08x}", expected, found)
+            }
+            SnapshotError::Corrupt(message) => write!(f, "corrupt snapshot: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<std::io::Error> for SnapshotError {
+    fn from(e: std::io::Error) -> Self {
+        SnapshotError::Io(e)
+    }
+}
+
+/**************************************************************
+*
+*   The size and modification time (unix seconds) of the input
+*   file the graphs were built from, a snapshot whose stamp differs
+*   from the current file is stale
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SourceStamp {
+    pub size: u64,
+    pub modified: u64,
+}
+
+impl SourceStamp {
+    pub fn of(path: &str) -> Result<Self, std::io::Error> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or(0);
+        Ok(SourceStamp { size: metadata.len(), modified })
+    }
+}
+
+/**************************************************************
+*
+*   The content of a snapshot:
+*   source: the stamp of the input file
+*   settings: a description of the load options chosen by the
+*   caller, a snapshot built with other options is stale
+*   periods: the periods the graphs were built for
+*   graphs: the graph of every period
+*
+***************************************************************/
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub source: SourceStamp,
+    pub settings: String,
+    pub periods: PeriodSpec,
+    pub graphs: PeriodGraphs,
+}
+
+impl Snapshot {
+    /**************************************************************
+    *
+    *   Whether the snapshot was built from this very input with
+    *   these periods and settings
+    *
+    ***************************************************************/
+
+    pub fn matches(&self, source: SourceStamp, settings: &str, periods: &PeriodSpec) -> bool {
+        self.source == source && self.settings == settings && self.periods == *periods
+    }
+}
+
+fn put_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_f64(out: &mut Vec<u8>, value: f64) {
+    out.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_str(out: &mut Vec<u8>, value: &str) {
+    put_u32(out, value.len() as u32);
+    out.extend_from_slice(value.as_bytes());
+}
+
+fn put_flow(out: &mut Vec<u8>, flow: &TokenFlow) {
+    put_u64(out, flow.count as u64);
+    put_f64(out, flow.value);
+    put_f64(out, flow.min_value);
+    put_f64(out, flow.max_value);
+    put_u64(out, flow.first_timestamp);
+    put_u64(out, flow.last_timestamp);
+}
+
+fn put_graph(out: &mut Vec<u8>, graph: &TransactionGraph) {
+    put_u32(out, graph.node_count() as u32);
+    for node in graph.nodes() {
+        put_str(out, graph.address(node));
+    }
+    put_u32(out, graph.token_count() as u32);
+    for token in graph.tokens() {
+        put_str(out, graph.token(token));
+        put_str(out, graph.token_symbol(token));
+    }
+    put_u64(out, graph.edge_count() as u64);
+    for edge_id in 0..graph.edge_count() as EdgeId {
+        let edge = graph.edge_by_id(edge_id);
+        put_u32(out, graph.edge_source(edge_id));
+        put_u32(out, graph.edge_target(edge_id));
+        put_flow(out, &TokenFlow {
+            count: edge.count,
+            value: edge.total_value,
+            min_value: edge.min_value,
+            max_value: edge.max_value,
+            first_timestamp: edge.first_timestamp,
+            last_timestamp: edge.last_timestamp,
+        });
+        put_u32(out, edge.tokens.len() as u32);
+        for (token, flow) in &edge.tokens {
+            put_u32(out, *token);
+            put_flow(out, flow);
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Write the snapshot of the period graphs to path, the file is
+*   written next to it first and renamed so a reader never sees a
+*   half written snapshot
+*
+***************************************************************/
+
+pub fn save_snapshot(path: &Path, snapshot: &Snapshot) -> Result<(), Box<dyn std::error::Error>> {
+    let mut payload = Vec::new();
+    put_u64(&mut payload, snapshot.source.size);
+    put_u64(&mut payload, snapshot.source.modified);
+    put_str(&mut payload, &snapshot.settings);
+    put_u32(&mut payload, snapshot.periods.periods.len() as u32);
+    for period in &snapshot.periods.periods {
+        put_str(&mut payload, &period.name);
+        put_u64(&mut payload, period.start);
+        put_u64(&mut payload, period.end);
+    }
+    put_u32(&mut payload, snapshot.graphs.len() as u32);
+    for (name, graph) in &snapshot.graphs {
+        put_str(&mut payload, name);
+        put_graph(&mut payload, graph);
+    }
+
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let partial = path.with_extension("partial");
+    let mut writer = BufWriter::new(File::create(&partial)?);
+    writer.write_all(&SNAPSHOT_MAGIC)?;
+    writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+    writer.write_all(&(payload.len() as u64).to_le_bytes())?;
+    writer.write_all(&crc32fast::hash(&payload).to_le_bytes())?;
+    writer.write_all(&payload)?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    fs::rename(&partial, path)?;
+    Ok(())
+}
+
+/**************************************************************
+*
+*   A cursor over the snapshot bytes, reading past the end is a
+*   corrupt snapshot instead of a panic
+*
+***************************************************************/
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        let end = self.position.checked_add(len).filter(|&end| end <= self.bytes.len());
+        let Some(end) = end else {
+            return Err(SnapshotError::Corrupt(format!("truncated at byte {}", self.position)));
+        };
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> Result<f64, SnapshotError> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn str(&mut self) -> Result<String, SnapshotError> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| SnapshotError::Corrupt("string is not UTF-8".to_string()))
+    }
+
+    fn flow(&mut self) -> Result<TokenFlow, SnapshotError> {
+        Ok(TokenFlow {
+            count: self.u64()? as usize,
+            value: self.f64()?,
+            min_value: self.f64()?,
+            max_value: self.f64()?,
+            first_timestamp: self.u64()?,
+            last_timestamp: self.u64()?,
+        })
+    }
+
+    fn graph(&mut self) -> Result<TransactionGraph, SnapshotError> {
+        let node_count = self.u32()?;
+        let mut addresses = Vec::with_capacity(node_count as usize);
+        let mut address_ids = HashMap::with_capacity(node_count as usize);
+        for node in 0..node_count {
+            let address = self.str()?;
+            address_ids.insert(address.clone(), node as NodeId);
+            addresses.push(address);
+        }
+        let token_count = self.u32()?;
+        let mut tokens = Vec::with_capacity(token_count as usize);
+        let mut token_ids = HashMap::with_capacity(token_count as usize);
+        let mut token_symbols = Vec::with_capacity(token_count as usize);
+        for token in 0..token_count {
+            let contract = self.str()?;
+            token_ids.insert(contract.clone(), token as TokenId);
+            tokens.push(contract);
+            token_symbols.push(self.str()?);
+        }
+
+        let edge_count = self.u64()?;
+        let mut entries = Vec::new();
+        let mut previous = None;
+        for _ in 0..edge_count {
+            let key = (self.u32()?, self.u32()?);
+            if key.0 >= node_count || key.1 >= node_count || previous.is_some_and(|previous| previous >= key) {
+                return Err(SnapshotError::Corrupt(format!("edge {} -> {} out of order or range", key.0, key.1)));
+            }
+            previous = Some(key);
+            let total = self.flow()?;
+            let flow_count = self.u32()?;
+            let mut flows = Vec::new();
+            for _ in 0..flow_count {
+                let token = self.u32()?;
+                if token >= token_count {
+                    return Err(SnapshotError::Corrupt(format!("token {} out of range", token)));
+                }
+                flows.push((token, self.flow()?));
+            }
+            let edge = Edge {
+                count: total.count,
+                total_value: total.value,
+                min_value: total.min_value,
+                max_value: total.max_value,
+                first_timestamp: total.first_timestamp,
+                last_timestamp: total.last_timestamp,
+                tokens: flows,
+            };
+            entries.push((key, edge));
+        }
+        Ok(TransactionGraph::from_sorted_edges(addresses, address_ids, tokens, token_ids, token_symbols, entries))
+    }
+}
+
+/**************************************************************
+*
+*   Memory-map a snapshot and rebuild its graphs, the magic,
+*   format version and checksum are verified before the payload
+*   is decoded
+*
+***************************************************************/
+
+pub fn load_snapshot(path: &Path) -> Result<Snapshot, SnapshotError> {
+    let file = File::open(path)?;
+    // SAFETY: the map is only read while the file is open here, snapshots are replaced by rename, never rewritten in place
+    let map = unsafe { Mmap::map(&file)? };
+    let bytes: &[u8] = &map;
+
+    if bytes.len() < HEADER_LEN || bytes[..8] != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::NotASnapshot);
+    }
+    let mut header = Reader { bytes: &bytes[8..HEADER_LEN], position: 0 };
+    let version = header.u32()?;
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let payload_len = header.u64()?;
+    let expected = header.u32()?;
+    let payload = &bytes[HEADER_LEN..];
+    if payload.len() as u64 != payload_len {
+        return Err(SnapshotError::Corrupt(format!("payload of {} bytes, expected {}", payload.len(), payload_len)));
+    }
+    let found = crc32fast::hash(payload);
+    if found != expected {
+        return Err(SnapshotError::ChecksumMismatch { expected, found });
+    }
+
+    let mut reader = Reader { bytes: payload, position: 0 };
+    let source = SourceStamp { size: reader.u64()?, modified: reader.u64()? };
+    let settings = reader.str()?;
+    let period_count = reader.u32()?;
+    let mut periods = Vec::new();
+    for _ in 0..period_count {
+        let name = reader.str()?;
+        periods.push(Period::new(&name, reader.u64()?, reader.u64()?));
+    }
+    let graph_count = reader.u32()?;
+    let mut graphs = PeriodGraphs::new();
+    for _ in 0..graph_count {
+        let name = reader.str()?;
+        graphs.insert(name, reader.graph()?);
+    }
+    Ok(Snapshot { source, settings, periods: PeriodSpec { periods }, graphs })
+}
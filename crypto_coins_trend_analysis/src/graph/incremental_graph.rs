@@ -0,0 +1,227 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use crate::graph::transaction_graph::{NodeId, EdgeCounting};
+
+//IncrementalGraph
+
+/**************************************************************
+*
+*   A transaction graph over a moving time window, transfers are
+*   added as the window end moves forward and expired as its start
+*   moves forward, so the window graph is never rebuilt. The edge,
+*   transfer and node counts and the degree distribution are kept
+*   up to date on every change:
+*   pair_transfers: the live transfers of every (sender, receiver)
+*   out_degree, in_degree: the degree of every node under counting
+*   degree_histogram: total degree -> number of nodes, nodes with
+*   degree 0 have no live transfer and are not in the window
+*   live: the live transfers in the order they were inserted
+*   The transfers must be inserted in timestamp order. Addresses
+*   keep their NodeId after they expire so ids stay stable over
+*   the whole scan
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalGraph {
+    counting: EdgeCounting,
+    addresses: Vec<String>,
+    address_ids: HashMap<String, NodeId>,
+    pair_transfers: HashMap<(NodeId, NodeId), usize>,
+    out_degree: Vec<usize>,
+    in_degree: Vec<usize>,
+    degree_histogram: BTreeMap<usize, usize>,
+    active_nodes: usize,
+    transfer_count: usize,
+    live: VecDeque<(NodeId, NodeId, u64)>,
+}
+
+impl IncrementalGraph {
+    pub fn new(counting: EdgeCounting) -> Self {
+        IncrementalGraph { counting, ..IncrementalGraph::default() }
+    }
+
+    pub fn add_node(&mut self, address: &str) -> NodeId {
+        if let Some(&id) = self.address_ids.get(address) {
+            return id;
+        }
+        let id = self.addresses.len() as NodeId;
+        self.addresses.push(address.to_string());
+        self.address_ids.insert(address.to_string(), id);
+        self.out_degree.push(0);
+        self.in_degree.push(0);
+        id
+    }
+
+    /**************************************************************
+    *
+    *   Add a transfer, returns true when it is the first live
+    *   transfer between the pair, i.e. a new distinct edge
+    *
+    ***************************************************************/
+
+    pub fn insert(&mut self, source: NodeId, target: NodeId, timestamp: u64) -> bool {
+        debug_assert!(self.live.back().is_none_or(|&(_, _, last)| last <= timestamp));
+        self.live.push_back((source, target, timestamp));
+        self.transfer_count += 1;
+        let count = self.pair_transfers.entry((source, target)).or_insert(0);
+        *count += 1;
+        let new_edge = *count == 1;
+        if new_edge || self.counting == EdgeCounting::Transfers {
+            self.shift_degree(source, true, true);
+            self.shift_degree(target, false, true);
+        }
+        new_edge
+    }
+
+    /**************************************************************
+    *
+    *   Remove every transfer older than the timestamp, returns the
+    *   pairs whose last live transfer was removed
+    *
+    ***************************************************************/
+
+    pub fn expire_before(&mut self, timestamp: u64) -> Vec<(NodeId, NodeId)> {
+        let mut removed = Vec::new();
+        while let Some(&(source, target, time)) = self.live.front() {
+            if time >= timestamp {
+                break;
+            }
+            self.live.pop_front();
+            self.transfer_count -= 1;
+            let count = self.pair_transfers.get_mut(&(source, target)).expect("live transfer without its pair");
+            *count -= 1;
+            let gone = *count == 0;
+            if gone {
+                self.pair_transfers.remove(&(source, target));
+                removed.push((source, target));
+            }
+            if gone || self.counting == EdgeCounting::Transfers {
+                self.shift_degree(source, true, false);
+                self.shift_degree(target, false, false);
+            }
+        }
+        removed
+    }
+
+    /**************************************************************
+    *
+    *   Helper function to move one node in the degree histogram
+    *   when its in or out degree changes by one
+    *
+    ***************************************************************/
+
+    fn shift_degree(&mut self, node: NodeId, outgoing: bool, added: bool) {
+        let before = self.degree(node);
+        let side = if outgoing { &mut self.out_degree[node as usize] } else { &mut self.in_degree[node as usize] };
+        if added {
+            *side += 1;
+        } else {
+            *side -= 1;
+        }
+        let after = self.degree(node);
+
+        if before > 0 {
+            let nodes = self.degree_histogram.get_mut(&before).expect("degree missing from the histogram");
+            *nodes -= 1;
+            if *nodes == 0 {
+                self.degree_histogram.remove(&before);
+            }
+        } else {
+            self.active_nodes += 1;
+        }
+        if after > 0 {
+            *self.degree_histogram.entry(after).or_default() += 1;
+        } else {
+            self.active_nodes -= 1;
+        }
+    }
+
+    pub fn address(&self, node: NodeId) -> &str {
+        &self.addresses[node as usize]
+    }
+
+    /**************************************************************
+    *
+    *   The number of addresses ever added, the size needed by a
+    *   structure indexed by NodeId
+    *
+    ***************************************************************/
+
+    pub fn address_count(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn degree(&self, node: NodeId) -> usize {
+        self.out_degree[node as usize] + self.in_degree[node as usize]
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.active_nodes
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.pair_transfers.len()
+    }
+
+    pub fn transfer_count(&self) -> usize {
+        self.transfer_count
+    }
+
+    pub fn contains_edge(&self, source: NodeId, target: NodeId) -> bool {
+        self.pair_transfers.contains_key(&(source, target))
+    }
+
+    /**************************************************************
+    *
+    *   The density of the window graph, the same as compute_density
+    *   on a graph built from the live transfers
+    *
+    ***************************************************************/
+
+    pub fn density(&self) -> f64 {
+        let num_nodes = self.node_count();
+        let num_edges = match self.counting {
+            EdgeCounting::Distinct => self.edge_count(),
+            EdgeCounting::Transfers => self.transfer_count(),
+        };
+        if num_nodes < 2 {
+            return 0.0;
+        }
+        num_edges as f64 / (num_nodes as f64 * (num_nodes as f64 - 1.0))
+    }
+
+    /**************************************************************
+    *
+    *   Histogram of total degree -> number of nodes in the window
+    *
+    ***************************************************************/
+
+    pub fn degree_distribution(&self) -> &BTreeMap<usize, usize> {
+        &self.degree_histogram
+    }
+
+    /**************************************************************
+    *
+    *   The share of the total degree held by the top_k highest
+    *   degree nodes, read from the histogram without sorting
+    *
+    ***************************************************************/
+
+    pub fn top_degree_share(&self, top_k: usize) -> f64 {
+        let total: usize = self.degree_histogram.iter().map(|(degree, nodes)| degree * nodes).sum();
+        if total == 0 {
+            return 0.0;
+        }
+        let mut remaining = top_k;
+        let mut top = 0;
+        for (degree, nodes) in self.degree_histogram.iter().rev() {
+            let taken = remaining.min(*nodes);
+            top += degree * taken;
+            remaining -= taken;
+            if remaining == 0 {
+                break;
+            }
+        }
+        top as f64 / total as f64
+    }
+}
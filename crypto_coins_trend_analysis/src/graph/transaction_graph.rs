@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::data_cleaning_load::data_loader::Transaction;
+
+//NodeId, TokenId, EdgeId, TokenFlow, Edge, EdgeCounting, NodeCounts, TransactionGraph, GraphBuilder
+
+/**************************************************************
+*
+*   Addresses and tokens are interned into compact integer ids,
+*   so the algorithms never clone or hash the 42-character address
+*   strings, the string is only looked up again for display
+*
+***************************************************************/
+
+pub type NodeId = u32;
+pub type TokenId = u32;
+pub type EdgeId = u32;
+
+/**************************************************************
+*
+*   The volume moved in one token on one edge, the number of
+*   transfers and the summed value of those transfers, with the
+*   smallest and largest transfer and the first and last time, so a
+*   single-token subgraph has the same edge records as a graph loaded
+*   from that token alone
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TokenFlow {
+    pub count: usize,
+    pub value: f64,
+    pub min_value: f64,
+    pub max_value: f64,
+    pub first_timestamp: u64,
+    pub last_timestamp: u64,
+}
+
+impl TokenFlow {
+    pub fn new(value: f64, timestamp: u64) -> Self {
+        TokenFlow {
+            count: 1,
+            value,
+            min_value: value,
+            max_value: value,
+            first_timestamp: timestamp,
+            last_timestamp: timestamp,
+        }
+    }
+
+    pub fn add(&mut self, value: f64, timestamp: u64) {
+        self.merge(&TokenFlow::new(value, timestamp));
+    }
+
+    pub fn merge(&mut self, other: &TokenFlow) {
+        self.count += other.count;
+        self.value += other.value;
+        self.min_value = self.min_value.min(other.min_value);
+        self.max_value = self.max_value.max(other.max_value);
+        self.first_timestamp = self.first_timestamp.min(other.first_timestamp);
+        self.last_timestamp = self.last_timestamp.max(other.last_timestamp);
+    }
+}
+
+/**************************************************************
+*
+*   The aggregated edge record between one sender and one receiver,
+*   every transfer between the pair is folded into it so that no
+*   volume is lost when the same sender pays the same receiver again:
+*   count: number of transfers on this edge
+*   total_value: summed value of all transfers
+*   min_value, max_value: value of the smallest and largest single transfer
+*   first_timestamp, last_timestamp: time of earliest and latest transfer
+*   tokens: per-token breakdown, most edges only carry one token so
+*   a small vector is used instead of a map
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge {
+    pub count: usize,
+    pub total_value: f64,
+    pub min_value: f64,
+    pub max_value: f64,
+    pub first_timestamp: u64,
+    pub last_timestamp: u64,
+    pub tokens: Vec<(TokenId, TokenFlow)>,
+}
+
+impl Edge {
+    pub fn new(token: TokenId, value: f64, timestamp: u64) -> Self {
+        Edge::from_flow(token, &TokenFlow::new(value, timestamp))
+    }
+
+    pub fn from_flow(token: TokenId, flow: &TokenFlow) -> Self {
+        Edge {
+            count: flow.count,
+            total_value: flow.value,
+            min_value: flow.min_value,
+            max_value: flow.max_value,
+            first_timestamp: flow.first_timestamp,
+            last_timestamp: flow.last_timestamp,
+            tokens: vec![(token, flow.clone())],
+        }
+    }
+
+    pub fn add(&mut self, token: TokenId, value: f64, timestamp: u64) {
+        self.add_flow(token, &TokenFlow::new(value, timestamp));
+    }
+
+    pub fn add_flow(&mut self, token: TokenId, flow: &TokenFlow) {
+        self.count += flow.count;
+        self.total_value += flow.value;
+        self.min_value = self.min_value.min(flow.min_value);
+        self.max_value = self.max_value.max(flow.max_value);
+        self.first_timestamp = self.first_timestamp.min(flow.first_timestamp);
+        self.last_timestamp = self.last_timestamp.max(flow.last_timestamp);
+        match self.tokens.iter_mut().find(|(id, _)| *id == token) {
+            Some((_, existing)) => existing.merge(flow),
+            None => self.tokens.push((token, flow.clone())),
+        }
+    }
+
+    pub fn token_flow(&self, token: TokenId) -> Option<&TokenFlow> {
+        self.tokens.iter().find(|(id, _)| *id == token).map(|(_, flow)| flow)
+    }
+}
+
+/**************************************************************
+*
+*   Selects how edges are counted by the analysis functions:
+*   Distinct: every (sender, receiver) pair counts once
+*   Transfers: every individual transfer counts, so an edge
+*   with three transfers counts three times
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeCounting {
+    #[default]
+    Distinct,
+    Transfers,
+}
+
+impl EdgeCounting {
+    pub fn edge_count(&self, edge: &Edge) -> usize {
+        match self {
+            EdgeCounting::Distinct => 1,
+            EdgeCounting::Transfers => edge.count,
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The number of addresses in a graph by the role they play,
+*   every address seen on either side of a transfer is a node:
+*   senders: addresses with at least one out-going edge
+*   receivers: addresses with at least one in-coming edge
+*   send_only, receive_only: addresses on just one side, e.g.
+*   exchange deposit wallets are typically receive-only
+*   isolated: addresses added without any transfer
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct NodeCounts {
+    pub total: usize,
+    pub senders: usize,
+    pub receivers: usize,
+    pub send_only: usize,
+    pub receive_only: usize,
+    pub isolated: usize,
+}
+
+/**************************************************************
+*
+*   The transaction graph used in this project. Every address is a
+*   node with a NodeId, the edges are stored once in an array sorted
+*   by (source, target) and the adjacency is kept in compressed
+*   sparse row (CSR) form in both directions:
+*   out-edges of node n: EdgeIds out_offsets[n]..out_offsets[n + 1]
+*   in-edges of node n: in_edges[in_offsets[n]..in_offsets[n + 1]]
+*   The graph is immutable, it is created by GraphBuilder, only the
+*   display symbols of the tokens (their contract address until a
+*   token registry names them) could be set afterwards
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default)]
+pub struct TransactionGraph {
+    addresses: Vec<String>,
+    address_ids: HashMap<String, NodeId>,
+    tokens: Vec<String>,
+    token_ids: HashMap<String, TokenId>,
+    token_symbols: Vec<String>,
+    edges: Vec<Edge>,
+    edge_sources: Vec<NodeId>,
+    edge_targets: Vec<NodeId>,
+    out_offsets: Vec<usize>,
+    in_offsets: Vec<usize>,
+    in_edges: Vec<EdgeId>,
+}
+
+impl TransactionGraph {
+    /**************************************************************
+    *
+    *   Lay out the edges, sorted by (source, target), as the
+    *   outgoing and incoming adjacency arrays of the graph, shared
+    *   by the builder and the snapshot loader
+    *
+    ***************************************************************/
+
+    pub(crate) fn from_sorted_edges(
+        addresses: Vec<String>,
+        address_ids: HashMap<String, NodeId>,
+        tokens: Vec<String>,
+        token_ids: HashMap<String, TokenId>,
+        token_symbols: Vec<String>,
+        entries: Vec<((NodeId, NodeId), Edge)>,
+    ) -> TransactionGraph {
+        let node_count = addresses.len();
+        let mut out_offsets = vec![0; node_count + 1];
+        let mut in_offsets = vec![0; node_count + 1];
+        for ((source, target), _) in &entries {
+            out_offsets[*source as usize + 1] += 1;
+            in_offsets[*target as usize + 1] += 1;
+        }
+        for node in 0..node_count {
+            out_offsets[node + 1] += out_offsets[node];
+            in_offsets[node + 1] += in_offsets[node];
+        }
+
+        let mut edges = Vec::with_capacity(entries.len());
+        let mut edge_sources = Vec::with_capacity(entries.len());
+        let mut edge_targets = Vec::with_capacity(entries.len());
+        let mut in_edges = vec![0; entries.len()];
+        let mut in_cursor = in_offsets.clone();
+        for (index, ((source, target), edge)) in entries.into_iter().enumerate() {
+            in_edges[in_cursor[target as usize]] = index as EdgeId;
+            in_cursor[target as usize] += 1;
+            edges.push(edge);
+            edge_sources.push(source);
+            edge_targets.push(target);
+        }
+
+        TransactionGraph {
+            addresses,
+            address_ids,
+            tokens,
+            token_ids,
+            token_symbols,
+            edges,
+            edge_sources,
+            edge_targets,
+            out_offsets,
+            in_offsets,
+            in_edges,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn transfer_count(&self) -> usize {
+        self.edges.iter().map(|edge| edge.count).sum()
+    }
+
+    pub fn node_counts(&self) -> NodeCounts {
+        let mut counts = NodeCounts { total: self.node_count(), ..NodeCounts::default() };
+        for node in self.nodes() {
+            match (self.out_degree(node) > 0, self.in_degree(node) > 0) {
+                (true, true) => {
+                    counts.senders += 1;
+                    counts.receivers += 1;
+                }
+                (true, false) => {
+                    counts.senders += 1;
+                    counts.send_only += 1;
+                }
+                (false, true) => {
+                    counts.receivers += 1;
+                    counts.receive_only += 1;
+                }
+                (false, false) => counts.isolated += 1,
+            }
+        }
+        counts
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.addresses.len() as NodeId
+    }
+
+    pub fn address(&self, node: NodeId) -> &str {
+        &self.addresses[node as usize]
+    }
+
+    pub fn node_id(&self, address: &str) -> Option<NodeId> {
+        self.address_ids.get(address).copied()
+    }
+
+    pub fn token(&self, token: TokenId) -> &str {
+        &self.tokens[token as usize]
+    }
+
+    pub fn token_id(&self, token: &str) -> Option<TokenId> {
+        self.token_ids.get(token).copied()
+    }
+
+    pub fn token_count(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = TokenId> {
+        0..self.tokens.len() as TokenId
+    }
+
+    pub fn token_symbol(&self, token: TokenId) -> &str {
+        &self.token_symbols[token as usize]
+    }
+
+    pub fn set_token_symbol(&mut self, token: TokenId, symbol: &str) {
+        self.token_symbols[token as usize] = symbol.to_string();
+    }
+
+    /**************************************************************
+    *
+    *   Iterate the out-going edges of a node as (target, edge)
+    *
+    ***************************************************************/
+
+    pub fn out_edges(&self, node: NodeId) -> impl Iterator<Item = (NodeId, &Edge)> + '_ {
+        let range = self.out_offsets[node as usize]..self.out_offsets[node as usize + 1];
+        self.edge_targets[range.clone()].iter().copied().zip(self.edges[range].iter())
+    }
+
+    /**************************************************************
+    *
+    *   Iterate the in-coming edges of a node as (source, edge)
+    *
+    ***************************************************************/
+
+    pub fn in_edges(&self, node: NodeId) -> impl Iterator<Item = (NodeId, &Edge)> + '_ {
+        let range = self.in_offsets[node as usize]..self.in_offsets[node as usize + 1];
+        self.in_edges[range]
+            .iter()
+            .map(move |&edge| (self.edge_sources[edge as usize], &self.edges[edge as usize]))
+    }
+
+    /**************************************************************
+    *
+    *   The EdgeIds of the out-going and in-coming edges of a node,
+    *   used to look up per-edge data such as the edge costs
+    *
+    ***************************************************************/
+
+    pub fn out_edge_ids(&self, node: NodeId) -> impl Iterator<Item = EdgeId> {
+        self.out_offsets[node as usize] as EdgeId..self.out_offsets[node as usize + 1] as EdgeId
+    }
+
+    pub fn in_edge_ids(&self, node: NodeId) -> impl Iterator<Item = EdgeId> + '_ {
+        self.in_edges[self.in_offsets[node as usize]..self.in_offsets[node as usize + 1]].iter().copied()
+    }
+
+    pub fn edge_by_id(&self, edge: EdgeId) -> &Edge {
+        &self.edges[edge as usize]
+    }
+
+    pub fn edge_source(&self, edge: EdgeId) -> NodeId {
+        self.edge_sources[edge as usize]
+    }
+
+    pub fn edge_target(&self, edge: EdgeId) -> NodeId {
+        self.edge_targets[edge as usize]
+    }
+
+    pub fn out_degree(&self, node: NodeId) -> usize {
+        self.out_offsets[node as usize + 1] - self.out_offsets[node as usize]
+    }
+
+    pub fn in_degree(&self, node: NodeId) -> usize {
+        self.in_offsets[node as usize + 1] - self.in_offsets[node as usize]
+    }
+
+    pub fn edge(&self, source: NodeId, target: NodeId) -> Option<&Edge> {
+        self.out_edges(source).find(|(node, _)| *node == target).map(|(_, edge)| edge)
+    }
+
+    /**************************************************************
+    *
+    *   The subgraph of the transfers in the given tokens, only the
+    *   addresses that sent or received one of them are kept and the
+    *   edges only carry the flows of those tokens
+    *
+    ***************************************************************/
+
+    pub fn filter_tokens(&self, tokens: &[TokenId]) -> TransactionGraph {
+        let mut builder = GraphBuilder::new();
+        for edge_id in 0..self.edge_count() as EdgeId {
+            for (token, flow) in &self.edge_by_id(edge_id).tokens {
+                if tokens.contains(token) {
+                    builder.add_token_flow(
+                        self.address(self.edge_source(edge_id)),
+                        self.address(self.edge_target(edge_id)),
+                        self.token(*token),
+                        flow,
+                    );
+                }
+            }
+        }
+        let mut subgraph = builder.build();
+        for token in subgraph.tokens() {
+            let original = self.token_ids[subgraph.token(token)];
+            subgraph.token_symbols[token as usize] = self.token_symbol(original).to_string();
+        }
+        subgraph
+    }
+
+    pub fn token_subgraph(&self, token: TokenId) -> TransactionGraph {
+        self.filter_tokens(&[token])
+    }
+}
+
+/**************************************************************
+*
+*   The builder that collects transfers one by one, interning the
+*   addresses and tokens and folding parallel transfers into one
+*   edge, build() then freezes it into the CSR TransactionGraph
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default)]
+pub struct GraphBuilder {
+    addresses: Vec<String>,
+    address_ids: HashMap<String, NodeId>,
+    tokens: Vec<String>,
+    token_ids: HashMap<String, TokenId>,
+    edges: HashMap<(NodeId, NodeId), Edge>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        GraphBuilder::default()
+    }
+
+    pub fn add_node(&mut self, address: &str) -> NodeId {
+        if let Some(&id) = self.address_ids.get(address) {
+            return id;
+        }
+        let id = self.addresses.len() as NodeId;
+        self.addresses.push(address.to_string());
+        self.address_ids.insert(address.to_string(), id);
+        id
+    }
+
+    fn add_token(&mut self, token: &str) -> TokenId {
+        if let Some(&id) = self.token_ids.get(token) {
+            return id;
+        }
+        let id = self.tokens.len() as TokenId;
+        self.tokens.push(token.to_string());
+        self.token_ids.insert(token.to_string(), id);
+        id
+    }
+
+    pub fn add_transfer(&mut self, from_address: &str, to_address: &str, unit: &str, value: f64, timestamp: u64) {
+        let source = self.add_node(from_address);
+        let target = self.add_node(to_address);
+        let token = self.add_token(unit);
+        self.edges
+            .entry((source, target))
+            .and_modify(|edge| edge.add(token, value, timestamp))
+            .or_insert_with(|| Edge::new(token, value, timestamp));
+    }
+
+    /**************************************************************
+    *
+    *   Add the already aggregated transfers of one token between a
+    *   pair of addresses, used to build subgraphs
+    *
+    ***************************************************************/
+
+    pub fn add_token_flow(&mut self, from_address: &str, to_address: &str, unit: &str, flow: &TokenFlow) {
+        let source = self.add_node(from_address);
+        let target = self.add_node(to_address);
+        let token = self.add_token(unit);
+        self.edges
+            .entry((source, target))
+            .and_modify(|edge| edge.add_flow(token, flow))
+            .or_insert_with(|| Edge::from_flow(token, flow));
+    }
+
+    pub fn add_transaction(&mut self, from_address: &str, to_address: &str, transaction: &Transaction) {
+        self.add_transfer(from_address, to_address, &transaction.unit, transaction.value, transaction.timestamp);
+    }
+
+    pub fn build(self) -> TransactionGraph {
+        let mut entries: Vec<((NodeId, NodeId), Edge)> = self.edges.into_iter().collect();
+        entries.sort_by_key(|(key, _)| *key);
+        TransactionGraph::from_sorted_edges(
+            self.addresses,
+            self.address_ids,
+            self.tokens.clone(),
+            self.token_ids,
+            self.tokens,
+            entries,
+        )
+    }
+}
@@ -0,0 +1,81 @@
+//! Transaction graph analysis of ERC20 token transfers, the library
+//! behind the crypto_coins_trend_analysis binary.
+//!
+//! Load an export into one TransactionGraph per period with
+//! load_csv_with_summary, run the analyses of computation_analysis and
+//! connected_component on the graphs, and render their result structs
+//! with AnalysisReport. Edge lists, GraphML, JSON lines and Parquet files
+//! are read into the same graphs through the GraphLoader trait of
+//! data_cleaning_load::graph_loader:
+//!
+//! ```no_run
+//! use crypto_coins_trend_analysis::{load_csv_with_summary, LoadOptions, PeriodSpec};
+//! use crypto_coins_trend_analysis::computation_analysis::computation_algorithms::summarize_graphs;
+//! use crypto_coins_trend_analysis::EdgeCounting;
+//!
+//! let (graphs, _summary) = load_csv_with_summary("transfers.csv.gz", &PeriodSpec::luna_crash(), &LoadOptions::default())?;
+//! for summary in summarize_graphs(&graphs, EdgeCounting::Distinct, false) {
+//!     println!("{}: {} addresses, density {}", summary.period, summary.nodes.total, summary.density);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod computation_analysis {
+    pub mod computation_algorithms;
+    pub mod token_analysis;
+    pub mod temporal_analysis;
+    pub mod path_analysis;
+}
+
+pub mod data_cleaning_load {
+    pub mod data_loader;
+    pub mod period_config;
+    pub mod sampling;
+    pub mod input_stream;
+    pub mod column_mapping;
+    pub mod validation;
+    pub mod token_registry;
+    pub mod price_table;
+    pub mod graph_loader;
+}
+
+pub mod utility {
+    pub mod helper_algorithm;
+    pub mod edge_cost;
+}
+
+pub mod connected_component {
+    pub mod find_connected_component;
+}
+
+pub mod graph {
+    pub mod transaction_graph;
+    pub mod incremental_graph;
+    pub mod graph_snapshot;
+}
+
+pub mod reporting {
+    pub mod analysis_report;
+    pub mod graph_export;
+}
+
+pub use graph::transaction_graph::{TransactionGraph, GraphBuilder, NodeId, EdgeId, TokenId, Edge, TokenFlow, EdgeCounting};
+pub use graph::incremental_graph::IncrementalGraph;
+pub use graph::graph_snapshot::{Snapshot, SnapshotError, SourceStamp, save_snapshot, load_snapshot};
+pub use data_cleaning_load::data_loader::{load_csv_with_summary, load_csv_convert_graph, stream_transfers, LoadOptions, PeriodGraphs, Transaction};
+pub use data_cleaning_load::graph_loader::{GraphLoader, InputFormat, loader_for};
+pub use data_cleaning_load::period_config::{Period, PeriodSpec, WindowSpec};
+pub use data_cleaning_load::validation::LoadSummary;
+pub use utility::helper_algorithm::{dijkstra, shortest_path};
+pub use utility::edge_cost::{EdgeCost, EdgeWeights};
+pub use reporting::analysis_report::{AnalysisReport, OutputFormat};
+
+#[cfg(test)]
+mod tests {
+    pub mod utility_tests;
+    pub mod loader_tests;
+    pub mod graph_tests;
+    pub mod centrality_tests;
+    pub mod component_tests;
+    pub mod report_tests;
+}
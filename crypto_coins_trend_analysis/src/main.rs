@@ -1,51 +1,33 @@
-mod computation_analysis {
-    pub mod computation_algorithms;
+mod cli {
+    pub mod arguments;
+    pub mod commands;
 }
 
-mod data_cleaning_load {
-    pub mod data_loader;
-}
-
-mod utility {
-    pub mod helper_algorithm;
-}
-
-mod connected_component {
-    pub mod find_connected_component;
-}
-
-mod tests {
-    pub mod utility_tests;
-}
-
-use computation_analysis::computation_algorithms;
-use data_cleaning_load::data_loader;
-use utility::helper_algorithm;
-use connected_component::find_connected_component;
-use tests::utility_tests;
+use clap::Parser;
+use cli::arguments::Cli;
 
 /**************************************************************
 *
-*   Execution level of the project, used all the wrapper functions
-*   from other modules to generate analysis result
+*   Execution level of the project, parse the subcommand and run
+*   only the analysis it names, e.g.
+*
+*   crypto_coins_trend_analysis centrality ../selected_rows.csv --top 10
+*   crypto_coins_trend_analysis windows 6h:1h ../selected_rows.csv --format csv
+*
+*   see --help of every subcommand for its options, the analyses
+*   themselves live in the library (lib.rs)
 *
 ***************************************************************/
 
-
 fn main() {
-    let path: &str = "../selected_rows.csv";
-    let graphs = data_loader::load_csv_convert_graph(path);
-    println!("finished loading graph......");
-    match graphs {
-        Ok((graph_prior_crash, graph_during_crash, graph_after_crash)) => {
-            data_loader::display_graph(&graph_prior_crash, "Transaction Graph Before Crash");
-            data_loader::display_graph(&graph_during_crash, "Transaction Graph During Crash");
-            data_loader::display_graph(&graph_after_crash, "Transaction Graph After Crash");
-
-            computation_algorithms::analyze_graphs(&graph_prior_crash, &graph_during_crash, &graph_after_crash);
-            computation_algorithms::analyze_centrality_across_periods(&graph_prior_crash, &graph_during_crash, &graph_after_crash);
-            find_connected_component::analyze_largest_components(&graph_prior_crash, &graph_during_crash, &graph_after_crash);
-        },
-        Err(e) => eprintln!("Error loading graph: {}", e),
+    let cli = Cli::parse();
+    if let Err(e) = cli::commands::run(cli.command) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    pub mod cli_tests;
+}
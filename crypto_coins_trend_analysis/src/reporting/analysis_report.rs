@@ -0,0 +1,374 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::computation_analysis::computation_algorithms::{
+    GraphSummary, CentralityReport, display_graph_summaries, display_centrality_reports,
+};
+use crate::computation_analysis::temporal_analysis::{WindowMetrics, display_time_series};
+use crate::computation_analysis::path_analysis::{PathReport, display_path_reports};
+use crate::computation_analysis::token_analysis::{TokenReport, display_token_reports};
+use crate::connected_component::find_connected_component::{ComponentReport, display_component_reports};
+use crate::data_cleaning_load::column_mapping::format_timestamp;
+
+//OutputFormat, AnalysisReport, MetricRow, DistributionRow
+
+/**************************************************************
+*
+*   The renderers of the analysis results:
+*   Console: the human readable summary printed on stdout
+*   Json: one report.json with every result
+*   Csv: tidy tables, one observation per row
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Console,
+    Json,
+    Csv,
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "console" => Ok(OutputFormat::Console),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            _ => Err(format!("unknown output format '{}', expected console, json or csv", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   One row of metrics.csv, a scalar metric of one period or window
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricRow {
+    pub period: String,
+    pub metric: String,
+    pub value: f64,
+}
+
+/**************************************************************
+*
+*   One row of distributions.csv, the count of one histogram bin
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DistributionRow {
+    pub period: String,
+    pub distribution: String,
+    pub bin: String,
+    pub count: usize,
+}
+
+/**************************************************************
+*
+*   The results of one run, every analysis fills its own part and
+*   leaves the others empty. Renderers only read the report, so the
+*   console summary, the JSON and the CSV files always agree
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AnalysisReport {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub graphs: Vec<GraphSummary>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub centrality: Vec<CentralityReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<ComponentReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub time_series: Vec<WindowMetrics>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathReport>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<TokenReport>,
+}
+
+impl AnalysisReport {
+    /**************************************************************
+    *
+    *   The scalar metrics of every period in long form
+    *   (period, metric, value), windows are named by their start
+    *
+    ***************************************************************/
+
+    pub fn metric_rows(&self) -> Vec<MetricRow> {
+        let mut rows = Vec::new();
+        let mut push = |period: &str, metric: &str, value: f64| {
+            rows.push(MetricRow { period: period.to_string(), metric: metric.to_string(), value });
+        };
+        for summary in &self.graphs {
+            let period = summary.period.as_str();
+            let counts = &summary.nodes;
+            push(period, "nodes", counts.total as f64);
+            push(period, "senders", counts.senders as f64);
+            push(period, "receivers", counts.receivers as f64);
+            push(period, "send_only", counts.send_only as f64);
+            push(period, "receive_only", counts.receive_only as f64);
+            push(period, "isolated", counts.isolated as f64);
+            push(period, "edges", summary.edges as f64);
+            push(period, "transfers", summary.transfers as f64);
+            push(period, "density", summary.density);
+        }
+        for report in &self.centrality {
+            let period = report.period.as_str();
+            for (name, summary) in [("degree", &report.degree), ("closeness", &report.closeness), ("betweenness", &report.betweenness)] {
+                push(period, &format!("{}_centrality_mean", name), summary.mean);
+                push(period, &format!("{}_centrality_variance", name), summary.variance);
+            }
+        }
+        for report in &self.components {
+            let period = report.period.as_str();
+            push(period, "weak_components", report.weak.count as f64);
+            push(period, "largest_weak_component", report.weak.largest as f64);
+            push(period, "strong_components", report.strong.count as f64);
+            push(period, "largest_strong_component", report.strong.largest as f64);
+        }
+        for metrics in &self.time_series {
+            let period = format_timestamp(metrics.start);
+            push(&period, "edges", metrics.edges as f64);
+            push(&period, "transfers", metrics.transfers as f64);
+            push(&period, "active_addresses", metrics.active_addresses as f64);
+            push(&period, "density", metrics.density);
+            push(&period, "largest_component", metrics.largest_component as f64);
+            push(&period, "top_concentration", metrics.top_concentration);
+        }
+        rows
+    }
+
+    /**************************************************************
+    *
+    *   The degree, strength and component size histograms of every
+    *   period in long form (period, distribution, bin, count)
+    *
+    ***************************************************************/
+
+    pub fn distribution_rows(&self) -> Vec<DistributionRow> {
+        let mut rows = Vec::new();
+        let mut push = |period: &str, distribution: &str, bin: String, count: usize| {
+            rows.push(DistributionRow { period: period.to_string(), distribution: distribution.to_string(), bin, count });
+        };
+        for summary in &self.graphs {
+            for (name, histogram) in [
+                ("in_degree", &summary.in_degree),
+                ("out_degree", &summary.out_degree),
+                ("total_degree", &summary.total_degree),
+            ] {
+                for (bin, &count) in histogram {
+                    push(&summary.period, name, bin.to_string(), count);
+                }
+            }
+            for (name, histogram) in [("in_strength", &summary.in_strength), ("out_strength", &summary.out_strength)] {
+                for (bin, &count) in histogram {
+                    push(&summary.period, name, bin.to_string(), count);
+                }
+            }
+        }
+        for report in &self.components {
+            for (name, summary) in [("weak_component_size", &report.weak), ("strong_component_size", &report.strong)] {
+                for (size, &count) in &summary.size_distribution {
+                    push(&report.period, name, size.to_string(), count);
+                }
+            }
+        }
+        rows
+    }
+
+    /**************************************************************
+    *
+    *   The console renderer, the same summary the analyses print
+    *
+    ***************************************************************/
+
+    pub fn render_console(&self) {
+        if !self.tokens.is_empty() {
+            display_token_reports(&self.tokens);
+        }
+        if !self.graphs.is_empty() {
+            display_graph_summaries(&self.graphs);
+        }
+        if !self.centrality.is_empty() {
+            display_centrality_reports(&self.centrality);
+        }
+        if !self.components.is_empty() {
+            display_component_reports(&self.components);
+        }
+        if !self.time_series.is_empty() {
+            display_time_series(&self.time_series);
+        }
+        if !self.paths.is_empty() {
+            display_path_reports(&self.paths);
+        }
+    }
+
+    /**************************************************************
+    *
+    *   Write the whole report as dir/report.json
+    *
+    ***************************************************************/
+
+    pub fn write_json(&self, dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join("report.json");
+        serde_json::to_writer_pretty(fs::File::create(&path)?, self)?;
+        Ok(path)
+    }
+
+    /**************************************************************
+    *
+    *   Write the tidy CSV tables into dir, only the tables with rows:
+    *   metrics.csv: period, metric, value
+    *   distributions.csv: period, distribution, bin, count
+    *   centrality.csv: period, address, degree, closeness, betweenness
+    *   components.csv: period, kind, component, size, address for
+    *   the members of the largest components
+    *   paths.csv: period, from, to, cost, hops, path with the
+    *   addresses of the path joined by '>'
+    *   tokens.csv: period, token, symbol and the statistics of the
+    *   per-token comparison
+    *
+    ***************************************************************/
+
+    pub fn write_csv(&self, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        fs::create_dir_all(dir)?;
+        let mut written = Vec::new();
+
+        let metrics = self.metric_rows();
+        if !metrics.is_empty() {
+            written.push(write_rows(dir, "metrics.csv", &metrics)?);
+        }
+        let distributions = self.distribution_rows();
+        if !distributions.is_empty() {
+            written.push(write_rows(dir, "distributions.csv", &distributions)?);
+        }
+
+        if !self.centrality.is_empty() {
+            let path = dir.join("centrality.csv");
+            let mut writer = csv::Writer::from_path(&path)?;
+            writer.write_record(["period", "address", "degree", "closeness", "betweenness"])?;
+            for report in &self.centrality {
+                for node in &report.nodes {
+                    writer.write_record([
+                        report.period.as_str(),
+                        node.address.as_str(),
+                        &node.degree.to_string(),
+                        &node.closeness.to_string(),
+                        &node.betweenness.to_string(),
+                    ])?;
+                }
+            }
+            writer.flush()?;
+            written.push(path);
+        }
+
+        if !self.components.is_empty() {
+            let path = dir.join("components.csv");
+            let mut writer = csv::Writer::from_path(&path)?;
+            writer.write_record(["period", "kind", "component", "size", "address"])?;
+            for report in &self.components {
+                for (kind, summary) in [("weak", &report.weak), ("strong", &report.strong)] {
+                    for (id, members) in summary.top_members.iter().enumerate() {
+                        for address in members {
+                            let (id, size) = (id.to_string(), members.len().to_string());
+                            writer.write_record([report.period.as_str(), kind, &id, &size, address.as_str()])?;
+                        }
+                    }
+                }
+            }
+            writer.flush()?;
+            written.push(path);
+        }
+
+        if !self.paths.is_empty() {
+            let path = dir.join("paths.csv");
+            let mut writer = csv::Writer::from_path(&path)?;
+            writer.write_record(["period", "from", "to", "cost", "hops", "path"])?;
+            for report in &self.paths {
+                writer.write_record([
+                    report.period.as_str(),
+                    report.from.as_str(),
+                    report.to.as_str(),
+                    &report.cost.to_string(),
+                    &(report.path.len() - 1).to_string(),
+                    &report.path.join(">"),
+                ])?;
+            }
+            writer.flush()?;
+            written.push(path);
+        }
+
+        if !self.tokens.is_empty() {
+            let path = dir.join("tokens.csv");
+            let mut writer = csv::Writer::from_path(&path)?;
+            writer.write_record([
+                "period", "token", "symbol", "nodes", "edges", "transfers", "volume", "density",
+                "mean_degree", "max_degree", "weak_components", "largest_weak_share", "largest_strong",
+            ])?;
+            for report in &self.tokens {
+                let stats = &report.stats;
+                writer.write_record([
+                    report.period.as_str(),
+                    report.token.as_str(),
+                    report.symbol.as_str(),
+                    &stats.nodes.to_string(),
+                    &stats.edges.to_string(),
+                    &stats.transfers.to_string(),
+                    &stats.volume.to_string(),
+                    &stats.density.to_string(),
+                    &stats.mean_degree.to_string(),
+                    &stats.max_degree.to_string(),
+                    &stats.weak_components.to_string(),
+                    &stats.largest_weak_share.to_string(),
+                    &stats.largest_strong.to_string(),
+                ])?;
+            }
+            writer.flush()?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /**************************************************************
+    *
+    *   Render the report in every requested format, the files go
+    *   into dir, returns the paths of the written files
+    *
+    ***************************************************************/
+
+    pub fn render(&self, formats: &[OutputFormat], dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let mut written = Vec::new();
+        for format in formats {
+            match format {
+                OutputFormat::Console => self.render_console(),
+                OutputFormat::Json => written.push(self.write_json(dir)?),
+                OutputFormat::Csv => written.extend(self.write_csv(dir)?),
+            }
+        }
+        Ok(written)
+    }
+}
+
+/**************************************************************
+*
+*   Helper function to write serialisable rows as dir/name with
+*   a header taken from the field names
+*
+***************************************************************/
+
+fn write_rows<T: Serialize>(dir: &Path, name: &str, rows: &[T]) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let path = dir.join(name);
+    let mut writer = csv::Writer::from_path(&path)?;
+    for row in rows {
+        writer.serialize(row)?;
+    }
+    writer.flush()?;
+    Ok(path)
+}
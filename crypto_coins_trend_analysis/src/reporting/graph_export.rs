@@ -0,0 +1,592 @@
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use crate::data_cleaning_load::data_loader::PeriodGraphs;
+use crate::graph::transaction_graph::{TransactionGraph, NodeId, TokenId, TokenFlow, EdgeCounting};
+use crate::computation_analysis::computation_algorithms::{
+    compute_centrality_report, compute_degree_centrality, node_degree, normalize_degree, CentralityOptions, DegreeDirection,
+};
+use crate::connected_component::find_connected_component::weakly_connected_components;
+
+//file_stem, GraphFormat, Subgraph, NodeAttributes, node_attributes, select_nodes, write_edge_list,
+//write_graphml, write_gexf, write_dot, write_graph, ExportOptions, export_graphs, export_graphs_with
+
+/**************************************************************
+*
+*   The file name of a period graph, period names may contain the
+*   '/' of a token subgraph or the ':' of a window start, every
+*   character other than a letter, digit, '-' or '_' becomes '_'
+*
+***************************************************************/
+
+pub fn file_stem(period: &str) -> String {
+    period
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/**************************************************************
+*
+*   The file formats a graph is exported to:
+*   EdgeList: CSV with one row per token flow, <period>.edges.csv
+*   GraphMl: GraphML for Gephi, yEd, networkx or igraph
+*   Gexf: the native format of Gephi
+*   Dot: Graphviz
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    EdgeList,
+    GraphMl,
+    Gexf,
+    Dot,
+}
+
+impl GraphFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::EdgeList => "edges.csv",
+            GraphFormat::GraphMl => "graphml",
+            GraphFormat::Gexf => "gexf",
+            GraphFormat::Dot => "dot",
+        }
+    }
+}
+
+impl std::str::FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "edges" | "csv" => Ok(GraphFormat::EdgeList),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "gexf" => Ok(GraphFormat::Gexf),
+            "dot" => Ok(GraphFormat::Dot),
+            _ => Err(format!("unknown graph format '{}', expected edges, graphml, gexf or dot", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The part of a period graph that is exported:
+*   Full: every address
+*   LargestComponent: the largest weakly connected component
+*   Hubs(k): the ego networks of the k addresses of highest total
+*   degree, the hubs with every address they sent to or received
+*   from and the transfers among them
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Subgraph {
+    #[default]
+    Full,
+    LargestComponent,
+    Hubs(usize),
+}
+
+impl Subgraph {
+    /**************************************************************
+    *
+    *   The part of the file name telling the subgraphs apart
+    *
+    ***************************************************************/
+
+    pub fn suffix(&self) -> String {
+        match self {
+            Subgraph::Full => String::new(),
+            Subgraph::LargestComponent => ".largest".to_string(),
+            Subgraph::Hubs(k) => format!(".hubs{}", k),
+        }
+    }
+}
+
+impl std::str::FromStr for Subgraph {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            None if value == "full" => Ok(Subgraph::Full),
+            None if value == "largest-component" => Ok(Subgraph::LargestComponent),
+            Some(("hubs", k)) => k
+                .parse()
+                .map(Subgraph::Hubs)
+                .map_err(|_| format!("invalid hub count '{}' in '{}'", k, value)),
+            _ => Err(format!("unknown subgraph '{}', expected full, largest-component or hubs:<k>", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The attributes of one address written with the graph, always
+*   computed on the whole period graph so a subgraph shows the
+*   place of its addresses in the full network:
+*   in_degree, out_degree: counted as chosen by EdgeCounting
+*   degree_centrality: the normalised out-degree
+*   closeness, betweenness: only when centrality options are given
+*   component: the id of the weakly connected component, 0 is the
+*   largest
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NodeAttributes {
+    pub in_degree: usize,
+    pub out_degree: usize,
+    pub degree_centrality: f64,
+    pub closeness: Option<f64>,
+    pub betweenness: Option<f64>,
+    pub component: u32,
+}
+
+pub fn node_attributes(graph: &TransactionGraph, counting: EdgeCounting, centrality: Option<&CentralityOptions>) -> Vec<NodeAttributes> {
+    let components = weakly_connected_components(graph);
+    let report = centrality.map(|options| compute_centrality_report("", graph, &CentralityOptions { counting, ..*options }));
+    let degree_centrality = match &report {
+        Some(report) => report.nodes.iter().map(|node| node.degree).collect(),
+        None => normalize_degree(&compute_degree_centrality(graph, counting), graph.node_count().saturating_sub(1)),
+    };
+    graph
+        .nodes()
+        .map(|node| NodeAttributes {
+            in_degree: node_degree(graph, node, DegreeDirection::In, counting),
+            out_degree: node_degree(graph, node, DegreeDirection::Out, counting),
+            degree_centrality: degree_centrality[node as usize],
+            closeness: report.as_ref().map(|report| report.nodes[node as usize].closeness),
+            betweenness: report.as_ref().map(|report| report.nodes[node as usize].betweenness),
+            component: components.component_of[node as usize],
+        })
+        .collect()
+}
+
+/**************************************************************
+*
+*   Mark the addresses of the chosen subgraph, indexed by NodeId,
+*   hubs of equal degree are taken in NodeId order
+*
+***************************************************************/
+
+pub fn select_nodes(graph: &TransactionGraph, attributes: &[NodeAttributes], subgraph: Subgraph) -> Vec<bool> {
+    match subgraph {
+        Subgraph::Full => vec![true; graph.node_count()],
+        Subgraph::LargestComponent => attributes.iter().map(|node| node.component == 0).collect(),
+        Subgraph::Hubs(k) => {
+            let mut hubs: Vec<NodeId> = graph.nodes().collect();
+            hubs.sort_by_key(|&node| std::cmp::Reverse(attributes[node as usize].in_degree + attributes[node as usize].out_degree));
+            let mut kept = vec![false; graph.node_count()];
+            for &hub in hubs.iter().take(k) {
+                kept[hub as usize] = true;
+                for (neighbour, _) in graph.out_edges(hub).chain(graph.in_edges(hub)) {
+                    kept[neighbour as usize] = true;
+                }
+            }
+            kept
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Write the graph as an edge list CSV with one row for every
+*   token transferred between a pair of addresses:
+*   source,target,token,symbol,transfers,value,min_value,max_value,
+*   first_timestamp,last_timestamp
+*   token is the contract address, so tokens sharing a symbol stay
+*   apart when the file is loaded again
+*
+***************************************************************/
+
+pub fn write_edge_list(graph: &TransactionGraph, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    write_edge_rows(graph, &vec![true; graph.node_count()], path)
+}
+
+fn write_edge_rows(graph: &TransactionGraph, kept: &[bool], path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record([
+        "source", "target", "token", "symbol", "transfers", "value", "min_value", "max_value", "first_timestamp", "last_timestamp",
+    ])?;
+    for (node, target, token, flow) in kept_flows(graph, kept) {
+        writer.write_record([
+            graph.address(node),
+            graph.address(target),
+            graph.token(token),
+            graph.token_symbol(token),
+            &flow.count.to_string(),
+            &flow.value.to_string(),
+            &flow.min_value.to_string(),
+            &flow.max_value.to_string(),
+            &flow.first_timestamp.to_string(),
+            &flow.last_timestamp.to_string(),
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/**************************************************************
+*
+*   The token flows of the edges with both addresses in the
+*   subgraph, as (source, target, token, flow) in EdgeId order. Every
+*   format writes one edge per token so the value of each token is
+*   kept and the files load back into the same graph
+*
+***************************************************************/
+
+fn kept_flows<'a>(graph: &'a TransactionGraph, kept: &'a [bool]) -> impl Iterator<Item = (NodeId, NodeId, TokenId, &'a TokenFlow)> + 'a {
+    graph
+        .nodes()
+        .filter(move |&node| kept[node as usize])
+        .flat_map(move |node| graph.out_edges(node).map(move |(target, edge)| (node, target, edge)))
+        .filter(move |(_, target, _)| kept[*target as usize])
+        .flat_map(|(node, target, edge)| edge.tokens.iter().map(move |(token, flow)| (node, target, *token, flow)))
+}
+
+/**************************************************************
+*
+*   The attribute columns shared by the GraphML, GEXF and DOT
+*   writers, with their type and the value of one node or edge.
+*   An edge carries the summed value, transfers and smallest and
+*   largest transfer of one token between two addresses, token is
+*   the contract address
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttributeType {
+    Int,
+    Long,
+    Double,
+    Text,
+}
+
+impl AttributeType {
+    fn graphml(&self) -> &'static str {
+        match self {
+            AttributeType::Int => "int",
+            AttributeType::Long => "long",
+            AttributeType::Double => "double",
+            AttributeType::Text => "string",
+        }
+    }
+
+    fn gexf(&self) -> &'static str {
+        match self {
+            AttributeType::Int => "integer",
+            AttributeType::Long => "long",
+            AttributeType::Double => "double",
+            AttributeType::Text => "string",
+        }
+    }
+}
+
+fn node_columns(attributes: &[NodeAttributes]) -> Vec<(&'static str, AttributeType)> {
+    let mut columns = vec![
+        ("in_degree", AttributeType::Int),
+        ("out_degree", AttributeType::Int),
+        ("degree_centrality", AttributeType::Double),
+    ];
+    if attributes.first().is_some_and(|node| node.closeness.is_some()) {
+        columns.push(("closeness", AttributeType::Double));
+        columns.push(("betweenness", AttributeType::Double));
+    }
+    columns.push(("component", AttributeType::Int));
+    columns
+}
+
+fn node_values(node: &NodeAttributes) -> Vec<String> {
+    let mut values = vec![node.in_degree.to_string(), node.out_degree.to_string(), node.degree_centrality.to_string()];
+    if let (Some(closeness), Some(betweenness)) = (node.closeness, node.betweenness) {
+        values.push(closeness.to_string());
+        values.push(betweenness.to_string());
+    }
+    values.push(node.component.to_string());
+    values
+}
+
+const EDGE_COLUMNS: [(&str, AttributeType); 8] = [
+    ("value", AttributeType::Double),
+    ("transfers", AttributeType::Int),
+    ("token", AttributeType::Text),
+    ("symbol", AttributeType::Text),
+    ("min_value", AttributeType::Double),
+    ("max_value", AttributeType::Double),
+    ("first_timestamp", AttributeType::Long),
+    ("last_timestamp", AttributeType::Long),
+];
+
+fn edge_values(graph: &TransactionGraph, token: TokenId, flow: &TokenFlow) -> Vec<String> {
+    vec![
+        flow.value.to_string(),
+        flow.count.to_string(),
+        graph.token(token).to_string(),
+        graph.token_symbol(token).to_string(),
+        flow.min_value.to_string(),
+        flow.max_value.to_string(),
+        flow.first_timestamp.to_string(),
+        flow.last_timestamp.to_string(),
+    ]
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_dot(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/**************************************************************
+*
+*   Write the subgraph as GraphML, the address is the node label
+*
+***************************************************************/
+
+pub fn write_graphml(
+    graph: &TransactionGraph,
+    name: &str,
+    attributes: &[NodeAttributes],
+    kept: &[bool],
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let columns = node_columns(attributes);
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    writeln!(out, r#"  <key id="label" for="node" attr.name="label" attr.type="string"/>"#)?;
+    for (column, kind) in &columns {
+        writeln!(out, r#"  <key id="{0}" for="node" attr.name="{0}" attr.type="{1}"/>"#, column, kind.graphml())?;
+    }
+    for (column, kind) in &EDGE_COLUMNS {
+        writeln!(out, r#"  <key id="{0}" for="edge" attr.name="{0}" attr.type="{1}"/>"#, column, kind.graphml())?;
+    }
+    writeln!(out, r#"  <graph id="{}" edgedefault="directed">"#, escape_xml(name))?;
+    for node in graph.nodes().filter(|&node| kept[node as usize]) {
+        writeln!(out, r#"    <node id="n{}">"#, node)?;
+        writeln!(out, r#"      <data key="label">{}</data>"#, escape_xml(graph.address(node)))?;
+        for ((column, _), value) in columns.iter().zip(node_values(&attributes[node as usize])) {
+            writeln!(out, r#"      <data key="{}">{}</data>"#, column, value)?;
+        }
+        writeln!(out, "    </node>")?;
+    }
+    for (index, (source, target, token, flow)) in kept_flows(graph, kept).enumerate() {
+        writeln!(out, r#"    <edge id="e{}" source="n{}" target="n{}">"#, index, source, target)?;
+        for ((column, _), value) in EDGE_COLUMNS.iter().zip(edge_values(graph, token, flow)) {
+            writeln!(out, r#"      <data key="{}">{}</data>"#, column, escape_xml(&value))?;
+        }
+        writeln!(out, "    </edge>")?;
+    }
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")
+}
+
+/**************************************************************
+*
+*   Write the subgraph as GEXF 1.3, the value of the token is also the
+*   edge weight Gephi uses for layouts and the contract address is
+*   the kind of the parallel edges of a pair of addresses
+*
+***************************************************************/
+
+pub fn write_gexf(
+    graph: &TransactionGraph,
+    name: &str,
+    attributes: &[NodeAttributes],
+    kept: &[bool],
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let columns = node_columns(attributes);
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+    writeln!(out, "  <meta>")?;
+    writeln!(out, "    <creator>crypto_coins_trend_analysis</creator>")?;
+    writeln!(out, "    <description>{}</description>", escape_xml(name))?;
+    writeln!(out, "  </meta>")?;
+    writeln!(out, r#"  <graph defaultedgetype="directed" mode="static">"#)?;
+    writeln!(out, r#"    <attributes class="node">"#)?;
+    for (id, (column, kind)) in columns.iter().enumerate() {
+        writeln!(out, r#"      <attribute id="{}" title="{}" type="{}"/>"#, id, column, kind.gexf())?;
+    }
+    writeln!(out, "    </attributes>")?;
+    writeln!(out, r#"    <attributes class="edge">"#)?;
+    for (id, (column, kind)) in EDGE_COLUMNS.iter().enumerate() {
+        writeln!(out, r#"      <attribute id="{}" title="{}" type="{}"/>"#, id, column, kind.gexf())?;
+    }
+    writeln!(out, "    </attributes>")?;
+    writeln!(out, "    <nodes>")?;
+    for node in graph.nodes().filter(|&node| kept[node as usize]) {
+        writeln!(out, r#"      <node id="{}" label="{}">"#, node, escape_xml(graph.address(node)))?;
+        writeln!(out, "        <attvalues>")?;
+        for (id, value) in node_values(&attributes[node as usize]).iter().enumerate() {
+            writeln!(out, r#"          <attvalue for="{}" value="{}"/>"#, id, value)?;
+        }
+        writeln!(out, "        </attvalues>")?;
+        writeln!(out, "      </node>")?;
+    }
+    writeln!(out, "    </nodes>")?;
+    writeln!(out, "    <edges>")?;
+    for (index, (source, target, token, flow)) in kept_flows(graph, kept).enumerate() {
+        writeln!(
+            out,
+            r#"      <edge id="{}" source="{}" target="{}" kind="{}" weight="{}">"#,
+            index, source, target, escape_xml(graph.token(token)), flow.value
+        )?;
+        writeln!(out, "        <attvalues>")?;
+        for (id, value) in edge_values(graph, token, flow).iter().enumerate() {
+            writeln!(out, r#"          <attvalue for="{}" value="{}"/>"#, id, escape_xml(value))?;
+        }
+        writeln!(out, "        </attvalues>")?;
+        writeln!(out, "      </edge>")?;
+    }
+    writeln!(out, "    </edges>")?;
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</gexf>")
+}
+
+/**************************************************************
+*
+*   Write the subgraph as a Graphviz digraph, the attributes are
+*   written as node and edge attributes of the same name
+*
+***************************************************************/
+
+pub fn write_dot(
+    graph: &TransactionGraph,
+    name: &str,
+    attributes: &[NodeAttributes],
+    kept: &[bool],
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    let columns = node_columns(attributes);
+    writeln!(out, "digraph \"{}\" {{", escape_dot(name))?;
+    for node in graph.nodes().filter(|&node| kept[node as usize]) {
+        let values: Vec<String> = columns
+            .iter()
+            .zip(node_values(&attributes[node as usize]))
+            .map(|((column, _), value)| format!("{}={}", column, value))
+            .collect();
+        writeln!(out, "  n{} [label=\"{}\", {}];", node, escape_dot(graph.address(node)), values.join(", "))?;
+    }
+    for (source, target, token, flow) in kept_flows(graph, kept) {
+        let values: Vec<String> = EDGE_COLUMNS
+            .iter()
+            .zip(edge_values(graph, token, flow))
+            .map(|((column, kind), value)| match kind {
+                AttributeType::Text => format!("{}=\"{}\"", column, escape_dot(&value)),
+                _ => format!("{}={}", column, value),
+            })
+            .collect();
+        writeln!(out, "  n{} -> n{} [{}];", source, target, values.join(", "))?;
+    }
+    writeln!(out, "}}")
+}
+
+/**************************************************************
+*
+*   Write the subgraph of one period to path in the given format
+*
+***************************************************************/
+
+pub fn write_graph(
+    graph: &TransactionGraph,
+    name: &str,
+    attributes: &[NodeAttributes],
+    kept: &[bool],
+    format: GraphFormat,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if format == GraphFormat::EdgeList {
+        return write_edge_rows(graph, kept, path);
+    }
+    let mut out = BufWriter::new(File::create(path)?);
+    match format {
+        GraphFormat::GraphMl => write_graphml(graph, name, attributes, kept, &mut out)?,
+        GraphFormat::Gexf => write_gexf(graph, name, attributes, kept, &mut out)?,
+        GraphFormat::Dot => write_dot(graph, name, attributes, kept, &mut out)?,
+        GraphFormat::EdgeList => unreachable!(),
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/**************************************************************
+*
+*   The options of the graph export:
+*   formats: the files written for every period
+*   subgraph: the part of every period graph that is written
+*   counting: how parallel transfers count in the degrees
+*   centrality: also compute closeness and betweenness of every
+*   address when given, which costs a shortest path run per address
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExportOptions {
+    pub formats: Vec<GraphFormat>,
+    pub subgraph: Subgraph,
+    pub counting: EdgeCounting,
+    pub centrality: Option<CentralityOptions>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        ExportOptions {
+            formats: vec![GraphFormat::EdgeList],
+            subgraph: Subgraph::Full,
+            counting: EdgeCounting::default(),
+            centrality: None,
+        }
+    }
+}
+
+/**************************************************************
+*
+*   Write the graph of every period into dir as
+*   <period>.edges.csv, returns the paths of the written files
+*
+***************************************************************/
+
+pub fn export_graphs(graphs: &PeriodGraphs, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    export_graphs_with(graphs, dir, &ExportOptions::default())
+}
+
+/**************************************************************
+*
+*   Write the chosen subgraph of every period into dir once per
+*   format, as <period><subgraph>.<extension> e.g.
+*   before.largest.graphml, returns the paths of the written files.
+*   Periods whose names give the same file stem, such as "Crash 1"
+*   and "Crash_1", get a counter "-2", "-3"... in the order of the
+*   periods so no file overwrites another
+*
+***************************************************************/
+
+pub fn export_graphs_with(graphs: &PeriodGraphs, dir: &Path, options: &ExportOptions) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    let mut stems = HashSet::new();
+    for (period, graph) in graphs {
+        let mut stem = file_stem(period);
+        let mut counter = 1;
+        while !stems.insert(stem.clone()) {
+            counter += 1;
+            stem = format!("{}-{}", file_stem(period), counter);
+        }
+        let attributes = node_attributes(graph, options.counting, options.centrality.as_ref());
+        let kept = select_nodes(graph, &attributes, options.subgraph);
+        for &format in &options.formats {
+            let path = dir.join(format!("{}{}.{}", stem, options.subgraph.suffix(), format.extension()));
+            write_graph(graph, period, &attributes, &kept, format, &path)?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
@@ -0,0 +1,198 @@
+use crate::data_cleaning_load::data_loader::Transaction;
+use crate::graph::transaction_graph::{GraphBuilder, TransactionGraph, EdgeCounting};
+use crate::computation_analysis::computation_algorithms::{
+    compute_betweenness_centrality, compute_centrality, compute_closeness, normalize_betweenness,
+    BetweennessWeighting, ClosenessVariant, SourceSample,
+};
+use crate::utility::helper_algorithm::{dijkstra, with_thread_pool, PathDirection};
+use crate::utility::edge_cost::{EdgeCost, EdgeWeights, MIN_COST_FRACTION};
+
+fn build(edges: &[(&str, &str, f64)]) -> TransactionGraph {
+    let mut builder = GraphBuilder::new();
+    for (from, to, value) in edges {
+        builder.add_transaction(from, to, &Transaction::new(*value, "USDT".to_string(), 0));
+    }
+    builder.build()
+}
+
+fn betweenness_of(graph: &TransactionGraph, betweenness: &[f64], address: &str) -> f64 {
+    betweenness[graph.node_id(address).unwrap() as usize]
+}
+
+//Test for betweenness centrality in computation_algorithms module
+#[test]
+fn test_betweenness_unweighted() {
+    // A -> B -> C -> D chain plus a diamond D -> E -> G, D -> F -> G
+    let graph = build(&[
+        ("A", "B", 1.0), ("B", "C", 1.0), ("C", "D", 1.0),
+        ("D", "E", 1.0), ("D", "F", 1.0), ("E", "G", 1.0), ("F", "G", 1.0),
+    ]);
+    let betweenness = compute_betweenness_centrality(&graph, BetweennessWeighting::Unweighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), None);
+
+    // B lies on the paths from A to C, D, E, F, G
+    assert_eq!(betweenness_of(&graph, &betweenness, "B"), 5.0);
+    // D lies between {A, B, C} and {E, F, G}
+    assert_eq!(betweenness_of(&graph, &betweenness, "D"), 9.0);
+    // E and F share the 4 paths from {A, B, C, D} to G
+    assert_eq!(betweenness_of(&graph, &betweenness, "E"), 2.0);
+    assert_eq!(betweenness_of(&graph, &betweenness, "F"), 2.0);
+    assert_eq!(betweenness_of(&graph, &betweenness, "A"), 0.0);
+    assert_eq!(betweenness_of(&graph, &betweenness, "G"), 0.0);
+
+    let normalized = normalize_betweenness(&betweenness, graph.node_count());
+    assert_eq!(normalized[graph.node_id("D").unwrap() as usize], 9.0 / 30.0);
+}
+
+#[test]
+fn test_betweenness_weighted() {
+    // The direct A -> D transfer is more expensive than the path through B
+    let graph = build(&[("A", "B", 1.0), ("B", "D", 1.0), ("A", "C", 1.0), ("C", "D", 5.0), ("A", "D", 10.0)]);
+
+    let weighted = compute_betweenness_centrality(&graph, BetweennessWeighting::Weighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), None);
+    assert_eq!(betweenness_of(&graph, &weighted, "B"), 1.0);
+    assert_eq!(betweenness_of(&graph, &weighted, "C"), 0.0);
+
+    // Counting hops only, A -> D is direct
+    let unweighted = compute_betweenness_centrality(&graph, BetweennessWeighting::Unweighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), None);
+    assert_eq!(betweenness_of(&graph, &unweighted, "B"), 0.0);
+}
+
+#[test]
+fn test_betweenness_weighted_zero_cost_edge() {
+    // A reaches C through B or D at the same cost, the B -> D edge costs nothing
+    let graph = build(&[("A", "B", 1.0), ("A", "D", 1.0), ("B", "D", 0.0), ("B", "C", 2.0), ("D", "C", 2.0)]);
+    let weights = EdgeWeights::from_fn(&graph, |_, _, edge| edge.total_value);
+    let (b, d) = (graph.node_id("B").unwrap(), graph.node_id("D").unwrap());
+    let zero_edge = graph.out_edge_ids(b).find(|&edge| graph.edge_target(edge) == d).unwrap();
+    assert_eq!(weights.min_cost(), MIN_COST_FRACTION);
+    assert_eq!(weights.weight(zero_edge), MIN_COST_FRACTION);
+
+    // The zero-cost edge is a step, so A -> B -> D is not a second shortest path to D
+    // and C is reached by exactly two shortest paths, one through B and one through D
+    let weighted = compute_betweenness_centrality(&graph, BetweennessWeighting::Weighted, &weights, None);
+    assert_eq!(betweenness_of(&graph, &weighted, "B"), 0.5);
+    assert_eq!(betweenness_of(&graph, &weighted, "D"), 0.5);
+    assert!(weighted.iter().all(|value| value.is_finite()));
+
+    // With the value cost the zero-value transfer is not a path at all
+    let value = EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct);
+    assert_eq!(value.weight(zero_edge), f64::INFINITY);
+}
+
+#[test]
+fn test_betweenness_source_sampling() {
+    let graph = build(&[
+        ("A", "B", 1.0), ("B", "C", 1.0), ("C", "D", 1.0), ("D", "A", 1.0),
+        ("B", "E", 1.0), ("E", "F", 1.0), ("F", "B", 1.0),
+    ]);
+    let exact = compute_betweenness_centrality(&graph, BetweennessWeighting::Unweighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), None);
+
+    // Sampling every node gives the exact result
+    let all = SourceSample { sources: graph.node_count(), seed: 7 };
+    let full = compute_betweenness_centrality(&graph, BetweennessWeighting::Unweighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), Some(all));
+    assert_eq!(full, exact);
+
+    // The same seed draws the same sample
+    let half = SourceSample { sources: 3, seed: 7 };
+    let first = compute_betweenness_centrality(&graph, BetweennessWeighting::Unweighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), Some(half));
+    let second = compute_betweenness_centrality(&graph, BetweennessWeighting::Unweighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), Some(half));
+    assert_eq!(first, second);
+    assert!(first.iter().any(|&value| value > 0.0));
+}
+
+#[test]
+fn test_parallel_centrality_is_deterministic() {
+    // A graph large enough to be split over several betweenness chunks
+    let mut builder = GraphBuilder::new();
+    for i in 0..300u64 {
+        let from = format!("n{}", i % 150);
+        let to = format!("n{}", (i * 7 + 3) % 150);
+        builder.add_transaction(&from, &to, &Transaction::new((i % 13 + 1) as f64, "USDT".to_string(), i));
+    }
+    let graph = builder.build();
+
+    let run = |threads| {
+        with_thread_pool(Some(threads), || {
+            let (_, closeness) = compute_centrality(&graph, EdgeCounting::Distinct);
+            let betweenness = compute_betweenness_centrality(&graph, BetweennessWeighting::Weighted, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct), None);
+            (closeness, betweenness)
+        })
+    };
+
+    let single = run(1);
+    let multi = run(4);
+    assert_eq!(single, multi);
+    assert!(single.1.iter().any(|&value| value > 0.0));
+}
+
+//Test for closeness centrality in computation_algorithms module
+#[test]
+fn test_closeness_variants_on_disconnected_graph() {
+    // A only reaches B, C reaches D and E, D also pays E
+    let graph = build(&[("A", "B", 1.0), ("C", "D", 1.0), ("C", "E", 1.0), ("D", "E", 1.0)]);
+    let a = graph.node_id("A").unwrap() as usize;
+    let c = graph.node_id("C").unwrap() as usize;
+    let weights = EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct);
+    let closeness = |variant, direction| compute_closeness(&graph, &weights, variant, direction);
+
+    // Classic closeness ranks A above C because A reaches its single neighbour cheaply
+    let classic = closeness(ClosenessVariant::Classic, PathDirection::Outgoing);
+    assert_eq!(classic[a], 4.0);
+    assert_eq!(classic[c], 2.0);
+
+    // Harmonic and Wasserman-Faust account for how many addresses are reached
+    let harmonic = closeness(ClosenessVariant::Harmonic, PathDirection::Outgoing);
+    assert_eq!(harmonic[a], 0.25);
+    assert_eq!(harmonic[c], 0.5);
+    let wasserman_faust = closeness(ClosenessVariant::WassermanFaust, PathDirection::Outgoing);
+    assert_eq!(wasserman_faust[a], 0.25);
+    assert_eq!(wasserman_faust[c], 0.5);
+
+    // In-closeness measures how close the other addresses are to a node
+    let incoming = closeness(ClosenessVariant::Harmonic, PathDirection::Incoming);
+    assert_eq!(incoming[graph.node_id("E").unwrap() as usize], 0.5);
+    assert_eq!(incoming[graph.node_id("B").unwrap() as usize], 0.25);
+    assert_eq!(incoming[a], 0.0);
+}
+
+#[test]
+fn test_harmonic_closeness_ignores_zero_value_edges() {
+    // S spammed V with a zero-value transfer, the hub H paid three addresses
+    let graph = build(&[("S", "V", 0.0), ("H", "A", 5.0), ("H", "B", 10.0), ("H", "C", 20.0)]);
+    let weights = EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct);
+    let harmonic = compute_closeness(&graph, &weights, ClosenessVariant::Harmonic, PathDirection::Outgoing);
+    let (s, h) = (graph.node_id("S").unwrap() as usize, graph.node_id("H").unwrap() as usize);
+    assert_eq!(harmonic[s], 0.0);
+    assert_eq!(harmonic[h], (1.0 / 5.0 + 1.0 / 10.0 + 1.0 / 20.0) / 5.0);
+    assert!(harmonic[h] > harmonic[s]);
+}
+
+//Test for edge_cost module
+#[test]
+fn test_edge_cost_presets() {
+    let mut builder = GraphBuilder::new();
+    builder.add_transaction("S", "A", &Transaction::new(90.0, "USDT".to_string(), 100));
+    builder.add_transaction("S", "B", &Transaction::new(10.0, "USDT".to_string(), 100));
+    builder.add_transaction("A", "T", &Transaction::new(1.0, "USDT".to_string(), 400));
+    builder.add_transaction("B", "T", &Transaction::new(100.0, "USDT".to_string(), 150));
+    let graph = builder.build();
+    let s = graph.node_id("S").unwrap();
+    let t = graph.node_id("T").unwrap() as usize;
+    let weights = |cost: &str| EdgeWeights::new(&graph, cost.parse::<EdgeCost>().unwrap(), EdgeCounting::Distinct);
+    let distance = |cost: &str| dijkstra(&graph, s, &weights(cost))[t];
+
+    assert_eq!(distance("value"), 91.0);
+    assert_eq!(distance("hops"), 2.0);
+    assert_eq!(distance("inverse-value"), 1.0 / 10.0 + 1.0 / 100.0);
+    // 90% of S's outflow went to A, which forwarded all of its outflow at the smallest cost,
+    // a fraction of the cheapest positive cost
+    assert_eq!(weights("neg-log-share").min_cost(), -(0.9f64.ln()) * MIN_COST_FRACTION);
+    assert_eq!(distance("neg-log-share"), -(0.9f64.ln()) + weights("neg-log-share").min_cost());
+    // B forwarded 50 seconds after receiving, A waited 300 seconds, S never received so its edges cost the least
+    assert_eq!(distance("time-delay"), 50.0 * MIN_COST_FRACTION + 50.0);
+    assert!("unknown".parse::<EdgeCost>().is_err());
+
+    // Any cost function can be plugged in
+    let custom = EdgeWeights::from_fn(&graph, |_, target, _| if graph.address(target) == "A" { 0.5 } else { 2.0 });
+    assert_eq!(dijkstra(&graph, s, &custom)[t], 2.5);
+}
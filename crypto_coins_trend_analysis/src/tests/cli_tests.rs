@@ -0,0 +1,68 @@
+use clap::Parser;
+use crate::cli::arguments::{Cli, Command};
+use crypto_coins_trend_analysis::computation_analysis::computation_algorithms::ClosenessVariant;
+use crypto_coins_trend_analysis::data_cleaning_load::sampling::Sampling;
+use crypto_coins_trend_analysis::graph::transaction_graph::EdgeCounting;
+use crypto_coins_trend_analysis::reporting::analysis_report::OutputFormat;
+use crypto_coins_trend_analysis::data_cleaning_load::data_loader::PeriodGraphs;
+use crypto_coins_trend_analysis::graph::graph_snapshot::{Snapshot, SourceStamp};
+
+//Test for the subcommands of the cli module
+#[test]
+fn test_cli_subcommand_options() {
+    let cli = Cli::try_parse_from([
+        "analysis", "centrality", "transfers.csv.gz", "--period", "before:0:100", "--transfers",
+        "--closeness", "classic", "--top", "10", "--format", "json,csv", "--sample", "first:50",
+    ])
+    .unwrap();
+    let Command::Centrality { input, centrality, top } = cli.command else {
+        panic!("expected the centrality subcommand");
+    };
+    let options = centrality.options(&input, top);
+    assert_eq!(input.input, "transfers.csv.gz");
+    assert_eq!(input.period_spec().unwrap().periods[0].name, "before");
+    assert_eq!((options.counting, options.closeness, options.top_nodes), (EdgeCounting::Transfers, ClosenessVariant::Classic, 10));
+    assert_eq!(input.format, vec![OutputFormat::Json, OutputFormat::Csv]);
+    assert_eq!(input.load_options().unwrap().sampling, Sampling::First(50));
+
+    let cli = Cli::try_parse_from(["analysis", "stats", "transfers.csv"]).unwrap();
+    let Command::Load { input, .. } = cli.command else {
+        panic!("stats should be an alias of load");
+    };
+    assert_eq!(input.format, vec![OutputFormat::Console]);
+    assert_eq!(input.period_spec().unwrap().periods.len(), 3);
+}
+
+//Test for the argument errors of the cli module
+#[test]
+fn test_cli_rejects_invalid_arguments() {
+    assert!(Cli::try_parse_from(["analysis", "density"]).is_err());
+    assert!(Cli::try_parse_from(["analysis", "paths", "transfers.csv"]).is_err());
+    assert!(Cli::try_parse_from(["analysis", "windows", "6h", "transfers.csv"]).is_err());
+    assert!(Cli::try_parse_from(["analysis", "load", "transfers.csv", "--format", "xml"]).is_err());
+    assert!(Cli::try_parse_from(["analysis", "load", "transfers.csv", "--periods", "p.toml", "--period", "a:0:1"]).is_err());
+}
+
+//Test for the snapshot settings of the cli module
+#[test]
+fn test_snapshot_settings_follow_the_price_file() {
+    let prices = "test_cli_prices.csv";
+    std::fs::write(prices, "token,timestamp,price_usd\nUST,1651104000,1.00\n").unwrap();
+    let cli = Cli::try_parse_from(["analysis", "load", "transfers.csv", "--prices", prices]).unwrap();
+    let Command::Load { input, .. } = cli.command else {
+        panic!("expected the load subcommand");
+    };
+    let spec = input.period_spec().unwrap();
+    let before = input.snapshot_settings().unwrap();
+    let snapshot = Snapshot { source: SourceStamp { size: 1, modified: 2 }, settings: before.clone(), periods: spec.clone(), graphs: PeriodGraphs::new() };
+
+    // The same name and size, only the price changed
+    std::fs::write(prices, "token,timestamp,price_usd\nUST,1651104000,0.10\n").unwrap();
+    let after = input.snapshot_settings();
+    std::fs::remove_file(prices).expect("Failed to delete test price file");
+
+    let after = after.unwrap();
+    assert!(snapshot.matches(SourceStamp { size: 1, modified: 2 }, &before, &spec));
+    assert_ne!(before, after);
+    assert!(!snapshot.matches(SourceStamp { size: 1, modified: 2 }, &after, &spec));
+}
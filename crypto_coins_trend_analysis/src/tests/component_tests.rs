@@ -0,0 +1,98 @@
+use crate::data_cleaning_load::data_loader::Transaction;
+use crate::graph::transaction_graph::{GraphBuilder, TransactionGraph};
+use crate::connected_component::find_connected_component::{
+    strongly_connected_components, weakly_connected_components, UnionFind, RollbackUnionFind,
+    largest_components_over_slots,
+};
+
+fn build(edges: &[(&str, &str)]) -> TransactionGraph {
+    let mut builder = GraphBuilder::new();
+    for (from, to) in edges {
+        builder.add_transaction(from, to, &Transaction::new(1.0, "USDT".to_string(), 0));
+    }
+    builder.build()
+}
+
+//Test for union-find in connected_component module
+#[test]
+fn test_union_find() {
+    let mut union_find = UnionFind::new(4);
+    assert!(union_find.union(0, 1));
+    assert!(union_find.union(2, 1));
+    // Already joined through 1
+    assert!(!union_find.union(0, 2));
+    assert_eq!(union_find.find(0), union_find.find(2));
+    assert_ne!(union_find.find(0), union_find.find(3));
+}
+
+//Test for weakly connected components, direction of the edges is ignored
+#[test]
+fn test_weakly_connected_components() {
+    // A -> B <- C forms one component even though A cannot reach C, D -> E is a second one
+    let mut builder = GraphBuilder::new();
+    builder.add_transaction("D", "E", &Transaction::new(1.0, "USDT".to_string(), 0));
+    builder.add_transaction("A", "B", &Transaction::new(1.0, "USDT".to_string(), 0));
+    builder.add_transaction("C", "B", &Transaction::new(1.0, "USDT".to_string(), 0));
+    builder.add_node("F");
+    let graph = builder.build();
+
+    let components = weakly_connected_components(&graph);
+    assert_eq!(components.count(), 3);
+    assert_eq!(components.sizes, vec![3, 2, 1]);
+    assert_eq!(components.size_distribution().into_iter().collect::<Vec<_>>(), vec![(1, 1), (2, 1), (3, 1)]);
+
+    let id = |address: &str| components.component_of[graph.node_id(address).unwrap() as usize];
+    assert_eq!(id("A"), 0);
+    assert_eq!(id("C"), 0);
+    assert_eq!(id("D"), 1);
+    assert_eq!(id("F"), 2);
+
+    let mut largest = components.top_members(&graph, 1).remove(0);
+    largest.sort();
+    assert_eq!(largest, vec!["A", "B", "C"]);
+}
+
+//Test for strongly connected components, members must reach each other
+#[test]
+fn test_strongly_connected_components() {
+    // Cycle A -> B -> C -> A, cycle D <-> E, bridge C -> D and a tail E -> F
+    let graph = build(&[
+        ("A", "B"), ("B", "C"), ("C", "A"),
+        ("C", "D"), ("D", "E"), ("E", "D"), ("E", "F"),
+    ]);
+
+    let components = strongly_connected_components(&graph);
+    assert_eq!(components.sizes, vec![3, 2, 1]);
+
+    let id = |address: &str| components.component_of[graph.node_id(address).unwrap() as usize];
+    assert_eq!(id("A"), id("B"));
+    assert_eq!(id("B"), id("C"));
+    assert_eq!(id("D"), id("E"));
+    assert_ne!(id("C"), id("D"));
+    assert_eq!(components.members(id("F")), vec![graph.node_id("F").unwrap()]);
+
+    // The same graph is a single weakly connected component
+    assert_eq!(weakly_connected_components(&graph).count(), 1);
+}
+
+//Test for the rollback union-find and the offline components over windows
+#[test]
+fn test_rollback_union_find() {
+    let mut union_find = RollbackUnionFind::new(5);
+    assert!(union_find.union(0, 1));
+    let snapshot = union_find.snapshot();
+    assert!(union_find.union(1, 2));
+    assert!(union_find.union(3, 4));
+    assert!(!union_find.union(0, 2));
+    assert_eq!(union_find.largest(), 3);
+
+    union_find.rollback(snapshot);
+    assert_eq!(union_find.largest(), 2);
+    assert_eq!(union_find.find(0), union_find.find(1));
+    assert_ne!(union_find.find(1), union_find.find(2));
+    assert_ne!(union_find.find(3), union_find.find(4));
+
+    // 0-1 alive in slots 0..3, 1-2 in 1..2, 3-4 in 2..4, nothing alive in slot 4
+    let edges = [(0, 1, 0, 3), (1, 2, 1, 2), (3, 4, 2, 4)];
+    assert_eq!(largest_components_over_slots(5, &edges, 5), vec![2, 3, 2, 2, 1]);
+}
@@ -0,0 +1,359 @@
+use crate::data_cleaning_load::data_loader::Transaction;
+use crate::graph::transaction_graph::{GraphBuilder, TransactionGraph, EdgeCounting};
+use crate::computation_analysis::computation_algorithms::{
+    compute_density, compute_degree_distribution, compute_strength, compute_strength_distribution,
+    DegreeDirection, StrengthBin,
+};
+use crate::utility::helper_algorithm::dijkstra;
+use crate::utility::edge_cost::{EdgeCost, EdgeWeights};
+use crate::data_cleaning_load::data_loader::PeriodGraphs;
+use crate::computation_analysis::token_analysis::{compute_token_stats, filter_by_tokens, split_by_token};
+use crate::computation_analysis::temporal_analysis::{compute_time_series, compute_time_series_incremental, compute_window_metrics};
+use crate::graph::incremental_graph::IncrementalGraph;
+use crate::data_cleaning_load::period_config::WindowSpec;
+use crate::data_cleaning_load::period_config::{Period, PeriodSpec};
+use crate::graph::graph_snapshot::{load_snapshot, save_snapshot, Snapshot, SnapshotError, SourceStamp, SNAPSHOT_MAGIC};
+
+//Test for the transaction_graph module
+fn parallel_edge_graph() -> TransactionGraph {
+    let mut builder = GraphBuilder::new();
+
+    // A pays B three times in two tokens, B pays C once
+    builder.add_transaction("A", "B", &Transaction::new(10.0, "USDT".to_string(), 300));
+    builder.add_transaction("A", "B", &Transaction::new(2.0, "USDT".to_string(), 100));
+    builder.add_transaction("A", "B", &Transaction::new(5.0, "DAI".to_string(), 200));
+    builder.add_transaction("B", "C", &Transaction::new(1.0, "DAI".to_string(), 400));
+    builder.build()
+}
+
+#[test]
+fn test_add_transaction_keeps_every_transfer() {
+    let graph = parallel_edge_graph();
+    let a = graph.node_id("A").unwrap();
+    let b = graph.node_id("B").unwrap();
+    let edge = graph.edge(a, b).unwrap();
+
+    assert_eq!(graph.out_degree(a), 1);
+    assert_eq!(edge.count, 3);
+    assert_eq!(edge.total_value, 17.0);
+    assert_eq!(edge.min_value, 2.0);
+    assert_eq!(edge.first_timestamp, 100);
+    assert_eq!(edge.last_timestamp, 300);
+    let usdt = edge.token_flow(graph.token_id("USDT").unwrap()).unwrap();
+    assert_eq!(usdt.count, 2);
+    assert_eq!(usdt.value, 12.0);
+    assert_eq!(edge.token_flow(graph.token_id("DAI").unwrap()).unwrap().value, 5.0);
+}
+
+#[test]
+fn test_edge_counting_modes() {
+    let graph = parallel_edge_graph();
+    let a = graph.node_id("A").unwrap();
+    let c = graph.node_id("C").unwrap() as usize;
+
+    // 2 distinct edges or 4 transfers over 3 * 2 possible edges
+    assert_eq!(compute_density(&graph, EdgeCounting::Distinct), 2.0 / 6.0);
+    assert_eq!(compute_density(&graph, EdgeCounting::Transfers), 4.0 / 6.0);
+
+    let distinct = compute_degree_distribution(&graph, DegreeDirection::Out, EdgeCounting::Distinct);
+    assert_eq!(distinct.get(&1), Some(&2));
+    let transfers = compute_degree_distribution(&graph, DegreeDirection::Out, EdgeCounting::Transfers);
+    assert_eq!(transfers.get(&3), Some(&1));
+    assert_eq!(transfers.get(&1), Some(&1));
+
+    // Distinct edges weigh their total value, transfers their cheapest one
+    assert_eq!(dijkstra(&graph, a, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Distinct))[c], 18.0);
+    assert_eq!(dijkstra(&graph, a, &EdgeWeights::new(&graph, EdgeCost::Value, EdgeCounting::Transfers))[c], 3.0);
+}
+
+#[test]
+fn test_csr_adjacency_both_directions() {
+    let mut builder = GraphBuilder::new();
+    builder.add_transaction("hub", "x", &Transaction::new(1.0, "USDC".to_string(), 1));
+    builder.add_transaction("y", "hub", &Transaction::new(2.0, "USDC".to_string(), 2));
+    builder.add_transaction("z", "hub", &Transaction::new(3.0, "USDC".to_string(), 3));
+    builder.add_transaction("z", "x", &Transaction::new(4.0, "USDC".to_string(), 4));
+    let graph = builder.build();
+    let hub = graph.node_id("hub").unwrap();
+    let x = graph.node_id("x").unwrap();
+
+    // Receive-only addresses get an id as well
+    assert_eq!(graph.node_count(), 4);
+    assert_eq!(graph.edge_count(), 4);
+    assert_eq!(graph.address(x), "x");
+
+    let out: Vec<&str> = graph.out_edges(hub).map(|(target, _)| graph.address(target)).collect();
+    assert_eq!(out, vec!["x"]);
+
+    let mut senders: Vec<(&str, f64)> = graph
+        .in_edges(hub)
+        .map(|(source, edge)| (graph.address(source), edge.total_value))
+        .collect();
+    senders.sort_by(|a, b| a.0.cmp(b.0));
+    assert_eq!(senders, vec![("y", 2.0), ("z", 3.0)]);
+    assert_eq!(graph.in_degree(x), 2);
+    assert_eq!(graph.out_degree(x), 0);
+}
+
+#[test]
+fn test_receive_only_addresses_are_nodes() {
+    let mut builder = GraphBuilder::new();
+    // Two users deposit to an exchange wallet that never sends in this period
+    builder.add_transaction("user1", "exchange", &Transaction::new(5.0, "USDT".to_string(), 1));
+    builder.add_transaction("user2", "exchange", &Transaction::new(7.0, "USDT".to_string(), 2));
+    builder.add_transaction("user1", "user2", &Transaction::new(1.0, "USDT".to_string(), 3));
+    builder.add_node("watched");
+    let graph = builder.build();
+
+    let counts = graph.node_counts();
+    assert_eq!(counts.total, 4);
+    assert_eq!(counts.senders, 2);
+    assert_eq!(counts.receivers, 2);
+    assert_eq!(counts.send_only, 1);
+    assert_eq!(counts.receive_only, 1);
+    assert_eq!(counts.isolated, 1);
+
+    // Density is over all 4 addresses, including the sink and the isolated one
+    assert_eq!(compute_density(&graph, EdgeCounting::Distinct), 3.0 / 12.0);
+    let distribution = compute_degree_distribution(&graph, DegreeDirection::Out, EdgeCounting::Distinct);
+    assert_eq!(distribution.get(&0), Some(&2));
+}
+
+#[test]
+fn test_in_out_total_degree_and_strength() {
+    let mut builder = GraphBuilder::new();
+    // Three wallets pay one hot wallet, which forwards once to a bridge
+    builder.add_transaction("w1", "hot", &Transaction::new(5.0, "USDC".to_string(), 1));
+    builder.add_transaction("w2", "hot", &Transaction::new(50.0, "USDC".to_string(), 2));
+    builder.add_transaction("w3", "hot", &Transaction::new(500.0, "USDC".to_string(), 3));
+    builder.add_transaction("w3", "hot", &Transaction::new(500.0, "USDC".to_string(), 4));
+    builder.add_transaction("hot", "bridge", &Transaction::new(1055.0, "USDC".to_string(), 5));
+    let graph = builder.build();
+    let hot = graph.node_id("hot").unwrap() as usize;
+
+    let in_degree = compute_degree_distribution(&graph, DegreeDirection::In, EdgeCounting::Distinct);
+    assert_eq!(in_degree.into_iter().collect::<Vec<_>>(), vec![(0, 3), (1, 1), (3, 1)]);
+    let in_transfers = compute_degree_distribution(&graph, DegreeDirection::In, EdgeCounting::Transfers);
+    assert_eq!(in_transfers.get(&4), Some(&1));
+    let out_degree = compute_degree_distribution(&graph, DegreeDirection::Out, EdgeCounting::Distinct);
+    assert_eq!(out_degree.into_iter().collect::<Vec<_>>(), vec![(0, 1), (1, 4)]);
+    let total_degree = compute_degree_distribution(&graph, DegreeDirection::Total, EdgeCounting::Distinct);
+    assert_eq!(total_degree.get(&4), Some(&1));
+
+    let in_strength = compute_strength(&graph, DegreeDirection::In);
+    let out_strength = compute_strength(&graph, DegreeDirection::Out);
+    assert_eq!(in_strength[hot], 1055.0);
+    assert_eq!(out_strength[hot], 1055.0);
+    assert_eq!(compute_strength(&graph, DegreeDirection::Total)[hot], 2110.0);
+
+    // Strength histogram by decade: 5 -> [1e0, 1e1), 50 -> [1e1, 1e2), 1000 and 1055 -> [1e3, 1e4)
+    let histogram = compute_strength_distribution(&out_strength);
+    assert_eq!(
+        histogram.into_iter().collect::<Vec<_>>(),
+        vec![
+            (StrengthBin::Zero, 1),
+            (StrengthBin::Decade(0), 1),
+            (StrengthBin::Decade(1), 1),
+            (StrengthBin::Decade(3), 2),
+        ]
+    );
+}
+
+//Test for token subgraphs
+fn mixed_token_graph() -> TransactionGraph {
+    let mut builder = GraphBuilder::new();
+    builder.add_transaction("A", "B", &Transaction::new(10.0, "0xust".to_string(), 100));
+    builder.add_transaction("A", "B", &Transaction::new(4.0, "0xusdc".to_string(), 50));
+    builder.add_transaction("A", "B", &Transaction::new(2.0, "0xust".to_string(), 300));
+    builder.add_transaction("B", "C", &Transaction::new(7.0, "0xusdc".to_string(), 200));
+    builder.add_transaction("C", "D", &Transaction::new(1.0, "0xust".to_string(), 400));
+    let mut graph = builder.build();
+    graph.set_token_symbol(graph.token_id("0xust").unwrap(), "UST");
+    graph.set_token_symbol(graph.token_id("0xusdc").unwrap(), "USDC");
+    graph
+}
+
+#[test]
+fn test_token_subgraph() {
+    let graph = mixed_token_graph();
+    let ust = graph.token_subgraph(graph.token_id("0xust").unwrap());
+
+    // Only the addresses that moved UST, with edges built from the UST transfers alone
+    assert_eq!(ust.node_count(), 4);
+    assert_eq!(ust.edge_count(), 2);
+    assert!(ust.edge(ust.node_id("B").unwrap(), ust.node_id("C").unwrap()).is_none());
+    let edge = ust.edge(ust.node_id("A").unwrap(), ust.node_id("B").unwrap()).unwrap();
+    assert_eq!((edge.count, edge.total_value, edge.min_value, edge.max_value), (2, 12.0, 2.0, 10.0));
+    assert_eq!((edge.first_timestamp, edge.last_timestamp), (100, 300));
+    assert_eq!(ust.token_symbol(0), "UST");
+
+    // Filtering on every token gives back the whole graph
+    let all = graph.filter_tokens(&[0, 1]);
+    assert_eq!((all.edge_count(), all.transfer_count()), (graph.edge_count(), graph.transfer_count()));
+}
+
+#[test]
+fn test_split_by_token() {
+    let mut graphs = PeriodGraphs::new();
+    graphs.insert("during".to_string(), mixed_token_graph());
+
+    let split = split_by_token(&graphs, &[]);
+    let names: Vec<&String> = split.keys().collect();
+    assert_eq!(names, vec!["during/UST", "during/USDC"]);
+
+    // Tokens are selected by symbol or contract address, ignoring case
+    let usdc = filter_by_tokens(&graphs, &["0xUSDC".to_string()]);
+    assert_eq!(usdc["during"].transfer_count(), 2);
+    assert_eq!(split_by_token(&graphs, &["usdc".to_string()]).len(), 1);
+
+    // UST: A -> B and C -> D are two weak components of two addresses
+    let stats = compute_token_stats(&split["during/UST"], EdgeCounting::Distinct);
+    assert_eq!((stats.nodes, stats.edges, stats.transfers), (4, 2, 3));
+    assert_eq!(stats.volume, 13.0);
+    assert_eq!((stats.weak_components, stats.largest_weak_share, stats.largest_strong), (2, 0.5, 1));
+}
+
+#[test]
+fn test_split_by_token_with_shared_symbol() {
+    // A bridged UST under another contract address and an unknown token named like a known one
+    let mut builder = GraphBuilder::new();
+    builder.add_transaction("A", "B", &Transaction::new(10.0, "0xust".to_string(), 100));
+    builder.add_transaction("B", "C", &Transaction::new(3.0, "0xwormhole".to_string(), 200));
+    builder.add_transaction("C", "D", &Transaction::new(1.0, "0xusdc".to_string(), 300));
+    let mut graph = builder.build();
+    graph.set_token_symbol(graph.token_id("0xust").unwrap(), "UST");
+    graph.set_token_symbol(graph.token_id("0xwormhole").unwrap(), "UST");
+    graph.set_token_symbol(graph.token_id("0xusdc").unwrap(), "USDC");
+    let mut graphs = PeriodGraphs::new();
+    graphs.insert("during".to_string(), graph);
+
+    let split = split_by_token(&graphs, &[]);
+    let names: Vec<&String> = split.keys().collect();
+    assert_eq!(names, vec!["during/UST@0xust", "during/UST@0xwormhole", "during/USDC"]);
+    assert_eq!(split["during/UST@0xwormhole"].transfer_count(), 1);
+}
+
+//Test for temporal_analysis module
+#[test]
+fn test_window_metrics() {
+    // A hub H trading with 12 addresses and one separate pair X -> Y
+    let mut builder = GraphBuilder::new();
+    for index in 0..12 {
+        builder.add_transaction("H", &format!("N{}", index), &Transaction::new(1.0, "USDT".to_string(), 100));
+    }
+    builder.add_transaction("X", "Y", &Transaction::new(1.0, "USDT".to_string(), 100));
+    builder.add_transaction("X", "Y", &Transaction::new(1.0, "USDT".to_string(), 150));
+    let graph = builder.build();
+
+    let window = Period::new("w", 0, 3600);
+    let metrics = compute_window_metrics(&graph, &window, EdgeCounting::Distinct);
+    assert_eq!((metrics.edges, metrics.transfers, metrics.active_addresses), (13, 14, 15));
+    assert_eq!(metrics.largest_component, 13);
+    // The 10 highest degrees: H with 12 and nine addresses with 1, out of a total degree of 26
+    assert_eq!(metrics.top_concentration, 21.0 / 26.0);
+
+    let mut graphs = PeriodGraphs::new();
+    graphs.insert("w".to_string(), graph);
+    graphs.insert("empty".to_string(), GraphBuilder::new().build());
+    let spec = PeriodSpec::new(vec![window, Period::new("empty", 3600, 7200)]).unwrap();
+    let series = compute_time_series(&graphs, &spec, EdgeCounting::Distinct);
+    assert_eq!(series.len(), 2);
+    assert_eq!(series[1].start, 3600);
+    assert_eq!((series[1].edges, series[1].top_concentration), (0, 0.0));
+}
+
+//Test for the incremental window graph against rebuilding every window
+#[test]
+fn test_incremental_time_series_matches_rebuilt_windows() {
+    // Two clusters that trade in turns, a bridge between them at 5000 and a self-transfer
+    let mut transfers: Vec<(String, String, u64)> = Vec::new();
+    for step in 0..40u64 {
+        let (prefix, offset) = if step % 8 < 4 { ("A", 0) } else { ("B", 1) };
+        transfers.push((format!("H{}", prefix), format!("{}{}", prefix, step % 5), step * 250 + offset));
+    }
+    for (from, to, timestamp) in [("HA", "HB", 5000), ("HA", "HB", 5100), ("A1", "A1", 6000)] {
+        transfers.push((from.to_string(), to.to_string(), timestamp));
+    }
+    transfers.reverse();
+
+    let windows = PeriodSpec::windows(0, 10000, &"1000s:250s".parse::<WindowSpec>().unwrap()).unwrap();
+    for counting in [EdgeCounting::Distinct, EdgeCounting::Transfers] {
+        let mut graphs = PeriodGraphs::new();
+        for window in &windows.periods {
+            let mut builder = GraphBuilder::new();
+            for (from, to, timestamp) in transfers.iter().rev() {
+                if window.contains(*timestamp) {
+                    builder.add_transaction(from, to, &Transaction::new(1.0, "USDT".to_string(), *timestamp));
+                }
+            }
+            graphs.insert(window.name.clone(), builder.build());
+        }
+
+        let mut graph = IncrementalGraph::new(counting);
+        let ids: Vec<_> = transfers.iter().map(|(from, to, timestamp)| (graph.add_node(from), graph.add_node(to), *timestamp)).collect();
+        let incremental = compute_time_series_incremental(graph, ids, &windows);
+        let rebuilt = compute_time_series(&graphs, &windows, counting);
+        assert_eq!(incremental.len(), rebuilt.len());
+        for (a, b) in incremental.iter().zip(&rebuilt) {
+            assert_eq!((a.start, a.edges, a.transfers, a.active_addresses, a.largest_component),
+                       (b.start, b.edges, b.transfers, b.active_addresses, b.largest_component));
+            assert!((a.density - b.density).abs() < 1e-12 && (a.top_concentration - b.top_concentration).abs() < 1e-12);
+        }
+    }
+}
+
+#[test]
+fn test_snapshot_round_trip() {
+    let path = std::path::Path::new("test_graph_round_trip.snap");
+    let mut graph = parallel_edge_graph();
+    graph.set_token_symbol(graph.token_id("DAI").unwrap(), "Dai");
+    let mut graphs = PeriodGraphs::new();
+    graphs.insert("before".to_string(), graph);
+    graphs.insert("during".to_string(), GraphBuilder::new().build());
+    let periods = PeriodSpec { periods: vec![Period::new("before", 0, 500), Period::new("during", 500, 1000)] };
+    let source = SourceStamp { size: 1234, modified: 99 };
+    let snapshot = Snapshot { source, settings: "sample=All".to_string(), periods: periods.clone(), graphs };
+    save_snapshot(path, &snapshot).expect("Failed to write the snapshot");
+
+    let loaded = load_snapshot(path).expect("Failed to read the snapshot");
+    std::fs::remove_file(path).expect("Failed to delete test snapshot");
+
+    assert!(loaded.matches(source, "sample=All", &periods));
+    assert!(!loaded.matches(SourceStamp { size: 1234, modified: 100 }, "sample=All", &periods));
+    let (before, during) = (&loaded.graphs["before"], &loaded.graphs["during"]);
+    assert_eq!(during.node_count(), 0);
+    assert_eq!(before.node_count(), 3);
+    assert_eq!(before.edge_count(), 2);
+    let (a, b, c) = (before.node_id("A").unwrap(), before.node_id("B").unwrap(), before.node_id("C").unwrap());
+    assert_eq!(before.edge(a, b), snapshot.graphs["before"].edge(a, b));
+    assert_eq!(before.in_edges(c).map(|(source, _)| source).collect::<Vec<_>>(), vec![b]);
+    assert_eq!(before.token_symbol(before.token_id("DAI").unwrap()), "Dai");
+}
+
+#[test]
+fn test_snapshot_detects_stale_and_damaged_files() {
+    let path = std::path::Path::new("test_graph_damaged.snap");
+    let snapshot = Snapshot {
+        source: SourceStamp::default(),
+        settings: String::new(),
+        periods: PeriodSpec { periods: vec![Period::new("before", 0, 500)] },
+        graphs: PeriodGraphs::from([("before".to_string(), parallel_edge_graph())]),
+    };
+    save_snapshot(path, &snapshot).expect("Failed to write the snapshot");
+    let bytes = std::fs::read(path).expect("Failed to read the snapshot");
+
+    let mut damaged = bytes.clone();
+    let last = damaged.len() - 1;
+    damaged[last] ^= 0xff;
+    std::fs::write(path, &damaged).unwrap();
+    assert!(matches!(load_snapshot(path), Err(SnapshotError::ChecksumMismatch { .. })));
+
+    let mut old_version = bytes.clone();
+    old_version[SNAPSHOT_MAGIC.len()..SNAPSHOT_MAGIC.len() + 4].copy_from_slice(&0u32.to_le_bytes());
+    std::fs::write(path, &old_version).unwrap();
+    assert!(matches!(load_snapshot(path), Err(SnapshotError::UnsupportedVersion(0))));
+
+    std::fs::write(path, b"source,target\n").unwrap();
+    assert!(matches!(load_snapshot(path), Err(SnapshotError::NotASnapshot)));
+    std::fs::remove_file(path).expect("Failed to delete test snapshot");
+}
@@ -0,0 +1,820 @@
+use crate::data_cleaning_load::data_loader::{load_csv_convert_graph, load_csv_with_summary, LoadOptions};
+use crate::data_cleaning_load::period_config::{parse_duration, Period, PeriodSpec, WindowSpec};
+use crate::data_cleaning_load::sampling::{PeriodSampler, Sampling};
+use crate::graph::transaction_graph::TransactionGraph;
+use crate::data_cleaning_load::input_stream::{open_input, Compression};
+use crate::data_cleaning_load::column_mapping::{format_timestamp, parse_timestamp, ColumnMapping};
+use csv::StringRecord;
+use crate::data_cleaning_load::validation::{is_valid_address, RejectReason, Validation, ValidationMode};
+use crate::data_cleaning_load::token_registry::{TokenInfo, TokenRegistry};
+use crate::data_cleaning_load::price_table::{PriceTable, ValueBasis};
+use crate::data_cleaning_load::graph_loader::{loader_for, EdgeListLoader, GraphLoader, GraphMlLoader, InputFormat, JsonLinesLoader, TransferCsvLoader};
+use crate::reporting::graph_export::{node_attributes, write_edge_list, write_graphml};
+use crate::graph::transaction_graph::{EdgeCounting, GraphBuilder, TokenFlow};
+use std::fs::File;
+use std::io::Write;
+
+//Test for period_config module
+#[test]
+fn test_period_spec_from_args() {
+    let args = vec!["usdc_depeg:1678406400:1678579200".to_string(), "recovery:1678579200:1678752000".to_string()];
+    let spec = PeriodSpec::from_args(&args).unwrap();
+
+    assert_eq!(spec.periods.len(), 2);
+    assert_eq!(spec.periods[0], Period::new("usdc_depeg", 1678406400, 1678579200));
+    assert!(spec.periods[1].contains(1678579200));
+    assert!(!spec.periods[0].contains(1678579200));
+
+    // Malformed, reversed and duplicated periods are rejected
+    assert!(PeriodSpec::from_args(&["bad".to_string()]).is_err());
+    assert!(PeriodSpec::from_args(&["a:20:10".to_string()]).is_err());
+    assert!(PeriodSpec::from_args(&["a:1:2".to_string(), "a:2:3".to_string()]).is_err());
+}
+
+#[test]
+fn test_period_spec_from_file() {
+    let toml_path = "test_periods.toml";
+    let mut file = File::create(toml_path).expect("Failed to create test TOML file");
+    writeln!(
+        file,
+        "[[periods]]\nname = \"ftx\"\nstart = 1667779200\nend = 1668384000"
+    ).expect("Failed to write to test TOML file");
+
+    let json_path = "test_periods.json";
+    let mut file = File::create(json_path).expect("Failed to create test JSON file");
+    writeln!(
+        file,
+        "{{\"periods\": [{{\"name\": \"ftx\", \"start\": 1667779200, \"end\": 1668384000}}]}}"
+    ).expect("Failed to write to test JSON file");
+
+    let from_toml = PeriodSpec::from_file(toml_path);
+    let from_json = PeriodSpec::from_file(json_path);
+
+    std::fs::remove_file(toml_path).expect("Failed to delete test TOML file");
+    std::fs::remove_file(json_path).expect("Failed to delete test JSON file");
+
+    assert_eq!(from_toml.unwrap(), from_json.unwrap());
+}
+
+//Test for data_loader module
+#[test]
+fn test_load_csv_any_number_of_periods() {
+    let file_path = "test_data_periods.csv";
+    let mut file = File::create(file_path).expect("Failed to create test CSV file");
+    writeln!(
+        file,
+        "field1,field2,from,to,timestamp,contract,value\n\
+         1,2,addr1,addr2,100,ETH,50.0\n\
+         3,4,addr2,addr3,200,BTC,100.0\n\
+         5,6,addr3,addr4,300,USDT,150.0\n\
+         7,8,addr4,addr5,400,USDT,150.0"
+    ).expect("Failed to write to test CSV file");
+
+    // Overlapping periods, a transaction is added to every matching period
+    let spec = PeriodSpec::new(vec![
+        Period::new("early", 100, 250),
+        Period::new("late", 200, 350),
+        Period::new("all", 0, 1000),
+        Period::new("empty", 1000, 2000),
+    ]).unwrap();
+    let result = load_csv_convert_graph(file_path, &spec, &LoadOptions::default());
+    std::fs::remove_file(file_path).expect("Failed to delete test CSV file");
+
+    let graphs = result.unwrap();
+    let names: Vec<&String> = graphs.keys().collect();
+    assert_eq!(names, vec!["early", "late", "all", "empty"]);
+    assert_eq!(graphs["early"].edge_count(), 2);
+    assert_eq!(graphs["late"].edge_count(), 2);
+    assert_eq!(graphs["all"].edge_count(), 4);
+    assert_eq!(graphs["empty"].node_count(), 0);
+}
+
+//Test for sampling module
+#[test]
+fn test_sampling_from_str() {
+    assert_eq!("all".parse::<Sampling>(), Ok(Sampling::All));
+    assert_eq!("first:10000".parse::<Sampling>(), Ok(Sampling::First(10000)));
+    assert_eq!("reservoir:500".parse::<Sampling>(), Ok(Sampling::Reservoir(500)));
+    assert_eq!("daily:20".parse::<Sampling>(), Ok(Sampling::StratifiedByDay(20)));
+    assert!("first".parse::<Sampling>().is_err());
+    assert!("random:5".parse::<Sampling>().is_err());
+}
+
+fn sample(sampling: Sampling, seed: u64, timestamps: &[u64]) -> PeriodSampler {
+    let mut sampler = PeriodSampler::new(sampling, seed);
+    for (row, &timestamp) in timestamps.iter().enumerate() {
+        sampler.offer(row, &format!("addr{}", row), &format!("addr{}", row + 1), "USDT", 1.0, timestamp);
+    }
+    sampler
+}
+
+#[test]
+fn test_sampling_modes() {
+    let timestamps: Vec<u64> = (0..100).collect();
+
+    let all = sample(Sampling::All, 42, &timestamps);
+    assert_eq!((all.kept(), all.offered()), (100, 100));
+
+    // First keeps the leading rows in file order, the legacy cap
+    let first = sample(Sampling::First(10), 42, &timestamps).build();
+    assert_eq!(first.edge_count(), 10);
+    assert!(first.node_id("addr9").is_some());
+    assert!(first.node_id("addr11").is_none());
+
+    // A reservoir keeps exactly its size, reproducible from the seed, not just the leading rows
+    let edges = |graph: &TransactionGraph| -> Vec<String> {
+        graph.nodes().filter(|&node| graph.out_degree(node) > 0).map(|node| graph.address(node).to_string()).collect()
+    };
+    let reservoir = sample(Sampling::Reservoir(10), 7, &timestamps).build();
+    assert_eq!(reservoir.edge_count(), 10);
+    assert_eq!(edges(&reservoir), edges(&sample(Sampling::Reservoir(10), 7, &timestamps).build()));
+    assert_ne!(edges(&reservoir), edges(&first));
+}
+
+#[test]
+fn test_sampling_stratified_by_day() {
+    // 50 transfers on day 0, 3 on day 1, a per-day sample of 5 keeps 5 + 3
+    let mut timestamps: Vec<u64> = vec![1000; 50];
+    timestamps.extend([86400, 90000, 100000]);
+
+    let sampler = sample(Sampling::StratifiedByDay(5), 42, &timestamps);
+    assert_eq!((sampler.kept(), sampler.offered()), (8, 53));
+    let graph = sampler.build();
+    assert_eq!(graph.transfer_count(), 8);
+    assert!(graph.node_id("addr50").is_some());
+    assert!(graph.node_id("addr52").is_some());
+}
+
+//Test for input_stream module
+#[test]
+fn test_load_compressed_csv_in_chunks() {
+    let csv = "field1,field2,from,to,timestamp,contract,value\n\
+               1,2,addr1,addr2,100,USDT,50.0\n\
+               3,4,addr2,addr3,200,USDT,100.0\n\
+               5,6,addr1,addr2,300,USDC,150.0\n";
+
+    let plain_path = "test_stream_plain.csv";
+    let gzip_path = "test_stream.csv.gz";
+    let zstd_path = "test_stream.csv.zst";
+    std::fs::write(plain_path, csv).expect("Failed to write test CSV file");
+    let mut encoder = flate2::write::GzEncoder::new(File::create(gzip_path).unwrap(), flate2::Compression::default());
+    encoder.write_all(csv.as_bytes()).unwrap();
+    encoder.finish().unwrap();
+    std::fs::write(zstd_path, zstd::encode_all(csv.as_bytes(), 0).unwrap()).expect("Failed to write test zstd file");
+
+    let spec = PeriodSpec::new(vec![Period::new("all", 0, 1000)]).unwrap();
+    // A chunk smaller than the file, the last chunk is only partly filled
+    let options = LoadOptions { chunk_size: 2, progress: false, ..LoadOptions::default() };
+    let compressions: Vec<Compression> = [plain_path, gzip_path, zstd_path]
+        .iter()
+        .map(|path| open_input(path).unwrap().compression)
+        .collect();
+    let results: Vec<_> = [plain_path, gzip_path, zstd_path]
+        .iter()
+        .map(|path| load_csv_convert_graph(path, &spec, &options))
+        .collect();
+    for path in [plain_path, gzip_path, zstd_path] {
+        std::fs::remove_file(path).expect("Failed to delete test file");
+    }
+
+    assert_eq!(compressions, vec![Compression::None, Compression::Gzip, Compression::Zstd]);
+    for result in results {
+        let graphs = result.unwrap();
+        assert_eq!(graphs["all"].edge_count(), 2);
+        assert_eq!(graphs["all"].transfer_count(), 3);
+    }
+}
+
+//Test for column_mapping module
+#[test]
+fn test_column_mapping_presets() {
+    let bigquery = StringRecord::from(vec![
+        "token_address", "from_address", "to_address", "value", "transaction_hash", "block_timestamp",
+    ]);
+    let columns = ColumnMapping::bigquery().resolve(&bigquery).unwrap();
+    assert_eq!((columns.from, columns.to, columns.timestamp, columns.token, columns.value), (1, 2, 5, 0, 3));
+
+    // Header names are matched ignoring case, a missing column is named in the error
+    let etherscan = StringRecord::from(vec!["Txhash", "UNIXTIMESTAMP", "From", "To", "TokenValue"]);
+    let error = ColumnMapping::etherscan().resolve(&etherscan).unwrap_err();
+    assert!(error.contains("'token'"), "{}", error);
+    assert!(error.contains("ContractAddress"), "{}", error);
+
+    assert!(ColumnMapping::preset("dune").is_ok());
+    assert!(ColumnMapping::preset("unknown").is_err());
+}
+
+#[test]
+fn test_parse_timestamp() {
+    assert_eq!(parse_timestamp("1651104000"), Ok(1651104000));
+    assert_eq!(parse_timestamp("2022-04-28 00:00:00 UTC"), Ok(1651104000));
+    assert_eq!(parse_timestamp("2022-04-28T00:00:00Z"), Ok(1651104000));
+    assert_eq!(parse_timestamp("2022-05-09 12:30:15.000 UTC"), Ok(1652099415));
+    assert_eq!(parse_timestamp("2024-02-29"), Ok(1709164800));
+    assert!(parse_timestamp("yesterday").is_err());
+    assert!(parse_timestamp("2022-13-01 00:00:00").is_err());
+    // Years too far out for epoch seconds are an error, not an overflow
+    assert!(parse_timestamp("99999999999999-01-01").unwrap_err().starts_with("invalid timestamp '99999999999999-01-01'"));
+    assert!(parse_timestamp("9223372036854775807-01-01 00:00:00").is_err());
+}
+
+#[test]
+fn test_load_csv_by_header_name() {
+    // A Dune export, the columns are in a different order than the Chartalist dataset
+    let file_path = "test_data_dune.csv";
+    let mut file = File::create(file_path).expect("Failed to create test CSV file");
+    writeln!(
+        file,
+        "evt_block_time,value,to,from,contract_address\n\
+         2022-04-28 01:00:00.000 UTC,50.0,addr2,addr1,USDT\n\
+         2022-04-28 02:00:00.000 UTC,70.0,addr3,addr2,USDT"
+    ).expect("Failed to write to test CSV file");
+
+    let spec = PeriodSpec::luna_crash();
+    let dune = LoadOptions { columns: ColumnMapping::dune(), progress: false, ..LoadOptions::default() };
+    let result = load_csv_convert_graph(file_path, &spec, &dune);
+    let missing = load_csv_convert_graph(file_path, &spec, &LoadOptions { progress: false, ..LoadOptions::default() });
+    std::fs::remove_file(file_path).expect("Failed to delete test CSV file");
+
+    let graph = &result.unwrap()["before"];
+    let (from, to) = (graph.node_id("addr1").unwrap(), graph.node_id("addr2").unwrap());
+    assert_eq!(graph.edge(from, to).unwrap().total_value, 50.0);
+    assert_eq!(graph.edge(from, to).unwrap().first_timestamp, 1651107600);
+    assert!(missing.unwrap_err().to_string().contains("'timestamp'"));
+}
+
+//Test for validation module
+#[test]
+fn test_is_valid_address() {
+    assert!(is_valid_address("0xdac17f958d2ee523a2206206994597c13d831ec7"));
+    assert!(is_valid_address("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48"));
+    assert!(!is_valid_address("dac17f958d2ee523a2206206994597c13d831ec7"));
+    assert!(!is_valid_address("0xdac17f958d2ee523a2206206994597c13d831ec"));
+    assert!(!is_valid_address("0xzac17f958d2ee523a2206206994597c13d831ec7"));
+}
+
+#[test]
+fn test_load_with_validation_and_quarantine() {
+    let a = "0x1111111111111111111111111111111111111111";
+    let b = "0x2222222222222222222222222222222222222222";
+    let file_path = "test_data_validation.csv";
+    let quarantine_path = "test_data_quarantine.csv";
+    let mut file = File::create(file_path).expect("Failed to create test CSV file");
+    writeln!(
+        file,
+        "block_number,index,from_address,to_address,time_stamp,contract_address,value\n\
+         1,0,{a},{b},1651104100,USDT,50.0\n\
+         2,0,addr1,{b},1651104200,USDT,50.0\n\
+         3,0,{a},{b},1651104300,USDT,-5.0\n\
+         4,0,{a},{b},not_a_time,USDT,5.0\n\
+         5,0,{a},{b},100,USDT,5.0\n\
+         6,0,{a},{a},1651104400,USDT,5.0\n\
+         7,0,{a}\n\
+         8,0,{b},{a},1651104500,USDT,10.0"
+    ).expect("Failed to write to test CSV file");
+
+    let spec = PeriodSpec::luna_crash();
+    let quarantine = LoadOptions {
+        progress: false,
+        validation: Validation { mode: ValidationMode::Quarantine(quarantine_path.to_string()), ..Validation::default() },
+        ..LoadOptions::default()
+    };
+    let result = load_csv_with_summary(file_path, &spec, &quarantine);
+    // The legacy mode aborts on the first unparsable row
+    let legacy = load_csv_convert_graph(file_path, &spec, &LoadOptions { progress: false, ..LoadOptions::default() });
+    let quarantined = std::fs::read_to_string(quarantine_path);
+    std::fs::remove_file(file_path).expect("Failed to delete test CSV file");
+    std::fs::remove_file(quarantine_path).expect("Failed to delete quarantine file");
+
+    let (graphs, summary) = result.unwrap();
+    assert_eq!((summary.rows_read, summary.accepted, summary.rejected_count()), (8, 2, 6));
+    for reason in [
+        RejectReason::InvalidAddress, RejectReason::InvalidValue, RejectReason::InvalidTimestamp,
+        RejectReason::TimestampOutOfRange, RejectReason::SelfTransfer, RejectReason::MalformedRow,
+    ] {
+        assert_eq!(summary.rejected[&reason], 1, "{}", reason);
+    }
+    assert_eq!(graphs["before"].transfer_count(), 2);
+
+    let quarantined = quarantined.unwrap();
+    assert_eq!(quarantined.lines().count(), 7);
+    assert!(quarantined.lines().next().unwrap().ends_with("value,row,reason"));
+    assert!(quarantined.contains("self_transfer"));
+
+    let legacy = legacy.unwrap_err().to_string();
+    assert!(legacy.contains("row 4"), "{}", legacy);
+}
+
+//Test for token_registry module
+#[test]
+fn test_token_registry() {
+    let registry = TokenRegistry::builtin();
+    let usdt = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+    let dai = "0x6B175474E89094C44Da98b954EedeAC495271d0F";
+
+    assert_eq!(registry.symbol(usdt), "USDT");
+    // Addresses are matched ignoring case
+    assert_eq!(registry.get(dai).unwrap().decimals, 18);
+    assert_eq!(registry.normalize(usdt, 2_500_000.0), 2.5);
+    assert_eq!(registry.normalize(dai, 3e18), 3.0);
+    assert_eq!(registry.normalize("0xunknown", 7.0), 7.0);
+    assert_eq!(registry.symbol("0xunknown"), "0xunknown");
+    assert_eq!(registry.get("0xd2877702675e6ceb975b4a1dff9fb7baf4c91ea9").unwrap().peg, None);
+
+    let toml_path = "test_tokens.toml";
+    std::fs::write(
+        toml_path,
+        "[[tokens]]\naddress = \"0x853d955aCEf822Db058eb8505911ED77F175b99e\"\nsymbol = \"FRAX\"\ndecimals = 18\npeg = \"USD\"\n\n\
+         [[tokens]]\naddress = \"0xdac17f958d2ee523a2206206994597c13d831ec7\"\nsymbol = \"USD₮\"\ndecimals = 6\n",
+    ).expect("Failed to write test TOML file");
+    let from_file = TokenRegistry::from_file(toml_path);
+    std::fs::remove_file(toml_path).expect("Failed to delete test TOML file");
+
+    let from_file = from_file.unwrap();
+    assert_eq!(from_file.len(), registry.len() + 1);
+    assert_eq!(
+        from_file.get("0x853d955acef822db058eb8505911ed77f175b99e"),
+        Some(&TokenInfo::new("0x853d955aCEf822Db058eb8505911ED77F175b99e", "FRAX", 18, Some("USD")))
+    );
+    // A file entry replaces the built-in one
+    assert_eq!(from_file.symbol(usdt), "USD₮");
+}
+
+#[test]
+fn test_load_normalizes_token_values() {
+    let file_path = "test_data_tokens.csv";
+    let mut file = File::create(file_path).expect("Failed to create test CSV file");
+    writeln!(
+        file,
+        "from,to,timestamp,contract,value\n\
+         addr1,addr2,1651104100,0xdac17f958d2ee523a2206206994597c13d831ec7,5000000\n\
+         addr1,addr2,1651104200,0x6b175474e89094c44da98b954eedeac495271d0f,2000000000000000000\n\
+         addr2,addr3,1651104300,OTHER,42"
+    ).expect("Failed to write to test CSV file");
+
+    let spec = PeriodSpec::luna_crash();
+    let normalized = load_csv_convert_graph(
+        file_path,
+        &spec,
+        &LoadOptions { progress: false, normalize_values: true, ..LoadOptions::default() },
+    );
+    // The Chartalist values are already in whole tokens, so they are kept by default
+    let raw = load_csv_convert_graph(file_path, &spec, &LoadOptions { progress: false, ..LoadOptions::default() });
+    std::fs::remove_file(file_path).expect("Failed to delete test CSV file");
+
+    let graph = &normalized.unwrap()["before"];
+    let edge = graph.edge(graph.node_id("addr1").unwrap(), graph.node_id("addr2").unwrap()).unwrap();
+    // 5 USDT + 2 DAI
+    assert_eq!(edge.total_value, 7.0);
+    let symbols: Vec<&str> = graph.tokens().map(|token| graph.token_symbol(token)).collect();
+    assert_eq!(symbols, vec!["USDT", "DAI", "OTHER"]);
+    assert_eq!(graph.token_id("0xdac17f958d2ee523a2206206994597c13d831ec7"), Some(0));
+
+    let raw = &raw.unwrap()["before"];
+    assert_eq!(raw.edge(raw.node_id("addr2").unwrap(), raw.node_id("addr3").unwrap()).unwrap().total_value, 42.0);
+    assert_eq!(raw.transfer_count(), 3);
+}
+
+//Test for price_table module
+#[test]
+fn test_price_table_nearest_prior() {
+    let registry = TokenRegistry::builtin();
+    let wluna = "0xd2877702675e6ceb975b4a1dff9fb7baf4c91ea9";
+    let ust = "0xa693b19d2931d498c5b318df961919bb4aee87a5";
+    let usdt = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+
+    let mut prices = PriceTable::new();
+    // Inserted out of order, looked up by the symbol of the registry
+    prices.insert("WLUNA", 200, 0.5);
+    prices.insert("WLUNA", 100, 80.0);
+    prices.insert(ust, 100, 0.6);
+
+    assert_eq!(prices.price_at("wluna", 99), None);
+    assert_eq!(prices.price_at("WLUNA", 100), Some(80.0));
+    assert_eq!(prices.price_at("WLUNA", 199), Some(80.0));
+    assert_eq!(prices.price_at("WLUNA", 5000), Some(0.5));
+
+    assert_eq!(prices.usd_value(&registry, wluna, 2.0, 150), Some(160.0));
+    // Listed stablecoins follow the table, the others are valued at their peg
+    assert_eq!(prices.usd_value(&registry, ust, 10.0, 150), Some(6.0));
+    assert_eq!(prices.usd_value(&registry, usdt, 10.0, 150), Some(10.0));
+    assert_eq!(prices.usd_value(&registry, wluna, 2.0, 50), None);
+    assert_eq!(prices.usd_value(&registry, "0xunknown", 2.0, 150), None);
+}
+
+#[test]
+fn test_load_usd_value_basis() {
+    let price_path = "test_prices.csv";
+    std::fs::write(
+        price_path,
+        "token,timestamp,price_usd\n\
+         WLUNA,2022-04-28 00:00:00,80.0\n\
+         WLUNA,2022-04-28 02:00:00,1.5\n",
+    ).expect("Failed to write test price file");
+    let file_path = "test_data_usd.csv";
+    let mut file = File::create(file_path).expect("Failed to create test CSV file");
+    writeln!(
+        file,
+        "from,to,timestamp,contract,value\n\
+         addr1,addr2,1651105000,WLUNA,2\n\
+         addr1,addr2,1651112000,WLUNA,2\n\
+         addr2,addr3,1651112000,NOPRICE,5"
+    ).expect("Failed to write to test CSV file");
+
+    let prices = PriceTable::from_csv(price_path);
+    let options = LoadOptions {
+        progress: false,
+        prices: prices.unwrap(),
+        value_basis: ValueBasis::Usd,
+        ..LoadOptions::default()
+    };
+    let result = load_csv_with_summary(file_path, &PeriodSpec::luna_crash(), &options);
+    std::fs::remove_file(price_path).expect("Failed to delete test price file");
+    std::fs::remove_file(file_path).expect("Failed to delete test CSV file");
+
+    let (graphs, summary) = result.unwrap();
+    let graph = &graphs["before"];
+    let edge = graph.edge(graph.node_id("addr1").unwrap(), graph.node_id("addr2").unwrap()).unwrap();
+    // 2 WLUNA at $80 and 2 WLUNA at $1.5
+    assert_eq!(edge.total_value, 163.0);
+    assert_eq!(summary.unpriced, 1);
+    assert!(graph.node_id("addr3").is_none());
+}
+
+//Test for the time windows of period_config module
+#[test]
+fn test_window_periods() {
+    assert_eq!(parse_duration("90m"), Ok(5400));
+    assert_eq!(parse_duration("6h"), Ok(21600));
+    assert_eq!(parse_duration("30"), Ok(30));
+    assert!(parse_duration("6y").is_err());
+    assert_eq!("6h:1h".parse::<WindowSpec>(), Ok(WindowSpec { width: 21600, step: 3600 }));
+    assert!("6h".parse::<WindowSpec>().is_err());
+    assert!("6h:0h".parse::<WindowSpec>().is_err());
+
+    // 6h sliding windows every hour over one day, only the 19 that fit entirely
+    let start = parse_timestamp("2022-05-09 00:00:00").unwrap();
+    let sliding = PeriodSpec::windows(start, start + 86400, &"6h:1h".parse().unwrap()).unwrap();
+    assert_eq!(sliding.periods.len(), 19);
+    assert_eq!(sliding.periods[1], Period::new("2022-05-09 01:00:00", start + 3600, start + 25200));
+    assert_eq!(sliding.periods[18].end, start + 86400);
+    assert_eq!(format_timestamp(start + 86399), "2022-05-09 23:59:59");
+
+    // Tumbling windows do not overlap, a window wider than the range is an error
+    let tumbling = PeriodSpec::windows(start, start + 86400, &"6h:6h".parse().unwrap()).unwrap();
+    assert_eq!(tumbling.periods.len(), 4);
+    assert!(PeriodSpec::windows(start, start + 3600, &"6h:1h".parse().unwrap()).is_err());
+
+    // Overflowing durations and windows are errors or stop the series, a zero step is rejected
+    assert!(parse_duration("99999999999999999w").is_err());
+    assert!(PeriodSpec::windows(start, start + 86400, &WindowSpec { width: 3600, step: 0 }).is_err());
+    let last = PeriodSpec::windows(u64::MAX - 10, u64::MAX, &WindowSpec { width: 5, step: 4 }).unwrap();
+    assert_eq!(last.periods.len(), 2);
+    assert_eq!(last.periods[1].end, u64::MAX - 1);
+    assert!(PeriodSpec::windows(u64::MAX - 1, u64::MAX, &WindowSpec { width: 5, step: 5 }).is_err());
+}
+
+//Test for the graph_loader module
+fn edge_summary(graph: &TransactionGraph) -> Vec<(String, String, String, usize, f64, u64)> {
+    let mut edges = Vec::new();
+    for node in graph.nodes() {
+        for (target, edge) in graph.out_edges(node) {
+            for (token, flow) in &edge.tokens {
+                let (from, to) = (graph.address(node).to_string(), graph.address(target).to_string());
+                edges.push((from, to, graph.token_symbol(*token).to_string(), flow.count, flow.value, flow.first_timestamp));
+            }
+        }
+    }
+    edges.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    edges
+}
+
+#[test]
+fn test_graph_loaders_read_the_same_graph() {
+    let mut builder = GraphBuilder::new();
+    builder.add_transfer("0xa", "0xb", "USDT", 10.0, 1651104000);
+    builder.add_transfer("0xa", "0xb", "USDT", 5.0, 1651104100);
+    builder.add_transfer("0xa", "0xb", "DAI", 1.0, 1651104200);
+    builder.add_transfer("0xb", "0xc", "DAI", 2.5, 1651200000);
+    let expected = builder.build();
+    let options = LoadOptions { normalize_values: false, ..LoadOptions::default() };
+
+    let edges_path = "test_graph_loader.edges.csv";
+    std::fs::write(
+        edges_path,
+        "# exported\nsource,target,token,transfers,value,first_timestamp,last_timestamp\n\
+         0xa,0xb,USDT,2,15,1651104000,1651104100\n0xa,0xb,DAI,1,1,1651104200,1651104200\n\
+         0xb,0xc,DAI,1,2.5,1651200000,1651200000\n",
+    )
+    .unwrap();
+    let jsonl_path = "test_graph_loader.jsonl";
+    std::fs::write(
+        jsonl_path,
+        "{\"from\": \"0xa\", \"to\": \"0xb\", \"token\": \"USDT\", \"value\": 15, \"transfers\": 2, \"timestamp\": 1651104000}\n\
+         {\"from\": \"0xa\", \"to\": \"0xb\", \"token\": \"DAI\", \"value\": 1, \"timestamp\": 1651104200}\n\
+         {\"from\": \"0xb\", \"to\": \"0xc\", \"token\": \"DAI\", \"value\": \"2.5\", \"timestamp\": \"2022-04-29 02:40:00\"}\n",
+    )
+    .unwrap();
+    let graphml_path = "test_graph_loader.graphml";
+    let attributes = node_attributes(&expected, EdgeCounting::Distinct, None);
+    write_graphml(&expected, "before", &attributes, &vec![true; expected.node_count()], &mut File::create(graphml_path).unwrap()).unwrap();
+
+    let loaders: Vec<Box<dyn GraphLoader>> = vec![
+        Box::new(EdgeListLoader { path: edges_path.to_string() }),
+        Box::new(JsonLinesLoader { path: jsonl_path.to_string() }),
+        Box::new(GraphMlLoader { path: graphml_path.to_string() }),
+    ];
+    let loaded: Vec<TransactionGraph> = loaders.iter().map(|loader| loader.load_graph(&options).unwrap().0).collect();
+    for path in [edges_path, jsonl_path, graphml_path] {
+        std::fs::remove_file(path).expect("Failed to delete test file");
+    }
+
+    // The two tokens of 0xa -> 0xb are read back as two flows, the GraphML has one edge per token
+    for graph in &loaded {
+        assert_eq!(edge_summary(graph), edge_summary(&expected));
+    }
+    let (graph, edge) = (&loaded[0], loaded[0].edge(0, 1).unwrap());
+    assert_eq!(graph.address(0), "0xa");
+    assert_eq!(edge.tokens.len(), 2);
+    assert_eq!((edge.min_value, edge.max_value, edge.last_timestamp), (1.0, 7.5, 1651104200));
+}
+
+fn token_flows(graph: &TransactionGraph) -> Vec<(String, String, String, TokenFlow)> {
+    let mut flows = Vec::new();
+    for node in graph.nodes() {
+        for (target, edge) in graph.out_edges(node) {
+            for (token, flow) in &edge.tokens {
+                let (from, to) = (graph.address(node).to_string(), graph.address(target).to_string());
+                flows.push((from, to, graph.token(*token).to_string(), flow.clone()));
+            }
+        }
+    }
+    flows.sort_by(|a, b| (&a.0, &a.1, &a.2).cmp(&(&b.0, &b.1, &b.2)));
+    flows
+}
+
+#[test]
+fn test_export_round_trip_keeps_every_token_flow() {
+    // Two contracts share the symbol UST, the transfers of a pair differ in size
+    let mut builder = GraphBuilder::new();
+    builder.add_transfer("0xa", "0xb", "0xust", 10.0, 1651104000);
+    builder.add_transfer("0xa", "0xb", "0xust", 2.0, 1651104100);
+    builder.add_transfer("0xa", "0xb", "0xwormhole", 3.0, 1651104200);
+    builder.add_transfer("0xb", "0xc", "0xwormhole", 0.5, 1651200000);
+    let mut expected = builder.build();
+    for token in expected.tokens() {
+        expected.set_token_symbol(token, "UST");
+    }
+    let options = LoadOptions { normalize_values: false, ..LoadOptions::default() };
+
+    let edges_path = "test_round_trip.edges.csv";
+    let graphml_path = "test_round_trip.graphml";
+    write_edge_list(&expected, std::path::Path::new(edges_path)).unwrap();
+    let attributes = node_attributes(&expected, EdgeCounting::Distinct, None);
+    write_graphml(&expected, "before", &attributes, &vec![true; expected.node_count()], &mut File::create(graphml_path).unwrap()).unwrap();
+    let from_edges = EdgeListLoader { path: edges_path.to_string() }.load_graph(&options);
+    let from_graphml = GraphMlLoader { path: graphml_path.to_string() }.load_graph(&options);
+    for path in [edges_path, graphml_path] {
+        std::fs::remove_file(path).expect("Failed to delete test file");
+    }
+
+    for (graph, _) in [from_edges.unwrap(), from_graphml.unwrap()] {
+        assert_eq!(token_flows(&graph), token_flows(&expected));
+        assert_eq!(graph.token_count(), 2);
+    }
+}
+
+#[test]
+fn test_edge_list_without_header_and_bad_rows() {
+    let a = "0x1111111111111111111111111111111111111111";
+    let b = "0x2222222222222222222222222222222222222222";
+    let c = "0x3333333333333333333333333333333333333333";
+    let path = "test_graph_loader_plain.txt";
+    std::fs::write(path, format!("% src dst weight\n{a} {b} 4.5\n{b} {c}\n{c}\n")).unwrap();
+    let spec = PeriodSpec::luna_crash();
+    let loader = loader_for(InputFormat::from_path(path), path).unwrap();
+
+    let strict = loader.load_periods(&spec, &LoadOptions::default());
+    let options = LoadOptions { validation: Validation { mode: ValidationMode::Skip, ..Validation::default() }, ..LoadOptions::default() };
+    let (graphs, summary) = loader.load_periods(&spec, &options).unwrap();
+    std::fs::remove_file(path).expect("Failed to delete test file");
+
+    assert_eq!(strict.unwrap_err().to_string(), "row 3: missing target address");
+    assert_eq!((summary.rows_read, summary.accepted, summary.rejected[&RejectReason::MalformedRow]), (3, 2, 1));
+    // Untimed edges belong to every period
+    for graph in graphs.values() {
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.edge(graph.node_id(a).unwrap(), graph.node_id(b).unwrap()).unwrap().total_value, 4.5);
+        assert_eq!(graph.token_symbol(0), "unknown");
+    }
+    assert_eq!(InputFormat::from_path("data/transfers.graphml.gz"), InputFormat::GraphMl);
+    assert_eq!(InputFormat::from_path("before.edges.csv"), InputFormat::Edges);
+    assert_eq!(InputFormat::from_path("../selected_rows.csv"), InputFormat::Transfers);
+}
+
+#[test]
+fn test_edge_list_quoted_fields() {
+    let path = "test_graph_loader_quoted.edges.csv";
+    std::fs::write(
+        path,
+        "# exported\nsource,target,token,value,timestamp\n\"0xa\",\"0xb\",\"Tether, USD\",15,1651104000\n0xb, 0xc ,DAI,2,1651104100\n",
+    )
+    .unwrap();
+    let tabs_path = "test_graph_loader_quoted.tsv";
+    std::fs::write(tabs_path, "0xa\t0xb\t3\t\"USD\tCoin\"\n").unwrap();
+    let options = LoadOptions { normalize_values: false, ..LoadOptions::default() };
+    let loaded = EdgeListLoader { path: path.to_string() }.load_graph(&options);
+    let tabs = EdgeListLoader { path: tabs_path.to_string() }.load_graph(&options);
+    for path in [path, tabs_path] {
+        std::fs::remove_file(path).expect("Failed to delete test file");
+    }
+
+    // The comma inside the quoted token does not split the record, so the value is read from its own column
+    let (graph, summary) = loaded.unwrap();
+    assert_eq!((summary.rows_read, summary.accepted), (2, 2));
+    assert_eq!(graph.token(0), "Tether, USD");
+    assert!(graph.edge(graph.node_id("0xa").unwrap(), graph.node_id("0xb").unwrap()).is_some());
+    assert_eq!(graph.edge(graph.node_id("0xb").unwrap(), graph.node_id("0xc").unwrap()).unwrap().total_value, 2.0);
+    let (graph, _) = tabs.unwrap();
+    assert_eq!((graph.token(0), graph.edge(0, 1).unwrap().total_value), ("USD\tCoin", 3.0));
+}
+
+#[test]
+fn test_graphml_edges_before_their_nodes() {
+    let path = "test_graph_loader_order.graphml";
+    std::fs::write(
+        path,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d0" for="node" attr.name="label" attr.type="string"/>
+  <key id="d1" for="edge" attr.name="value" attr.type="double"/>
+  <graph edgedefault="directed">
+    <edge source="n0" target="n1"><data key="d1">4</data></edge>
+    <edge source="n1" target="n2"/>
+    <node id="n0"><data key="d0">0xa</data></node>
+    <node id="n1"><data key="d0">0xb</data></node>
+    <node id="n2"><data key="d0">0xc</data></node>
+  </graph>
+</graphml>
+"#,
+    )
+    .unwrap();
+    let loaded = GraphMlLoader { path: path.to_string() }.load_graph(&LoadOptions { normalize_values: false, ..LoadOptions::default() });
+    std::fs::remove_file(path).expect("Failed to delete test file");
+
+    let (graph, summary) = loaded.unwrap();
+    assert_eq!(summary.accepted, 2);
+    let mut addresses: Vec<&str> = graph.nodes().map(|node| graph.address(node)).collect();
+    addresses.sort();
+    assert_eq!(addresses, vec!["0xa", "0xb", "0xc"]);
+    assert_eq!(graph.edge(graph.node_id("0xa").unwrap(), graph.node_id("0xb").unwrap()).unwrap().total_value, 4.0);
+    assert_eq!(graph.edge(graph.node_id("0xb").unwrap(), graph.node_id("0xc").unwrap()).unwrap().total_value, 1.0);
+}
+
+#[test]
+fn test_graph_loaders_check_rows_like_the_transfer_csv() {
+    let a = "0x1111111111111111111111111111111111111111";
+    let b = "0x2222222222222222222222222222222222222222";
+    let edges_path = "test_graph_loader_checks.edges.csv";
+    std::fs::write(
+        edges_path,
+        format!("source,target,value,timestamp\n{a},{b},50,1651104100\naddr1,{b},50,1651104200\n{a},{b},-5,1651104300\n{a},{a},5,1651104400\n{b},{a},10,1651104500\n"),
+    )
+    .unwrap();
+    let jsonl_path = "test_graph_loader_checks.jsonl";
+    std::fs::write(
+        jsonl_path,
+        format!(
+            "{{\"from\": \"{a}\", \"to\": \"{b}\", \"value\": 50}}\n{{\"from\": \"addr1\", \"to\": \"{b}\", \"value\": 50}}\n\
+             {{\"from\": \"{a}\", \"to\": \"{b}\", \"value\": -5}}\n{{\"from\": \"{a}\", \"to\": \"{a}\", \"value\": 5}}\n\
+             {{\"from\": \"{b}\", \"to\": \"{a}\", \"value\": 10}}\n"
+        ),
+    )
+    .unwrap();
+    let options = LoadOptions {
+        progress: false,
+        validation: Validation { mode: ValidationMode::Skip, ..Validation::default() },
+        ..LoadOptions::default()
+    };
+    let loaders: Vec<Box<dyn GraphLoader>> = vec![
+        Box::new(EdgeListLoader { path: edges_path.to_string() }),
+        Box::new(JsonLinesLoader { path: jsonl_path.to_string() }),
+    ];
+    let loaded: Vec<_> = loaders.iter().map(|loader| loader.load_graph(&options)).collect();
+    let unchecked = loaders[0].load_graph(&LoadOptions { progress: false, ..LoadOptions::default() });
+    let quarantine_path = "test_graph_loader_checks.quarantine.csv";
+    let quarantine = Validation { mode: ValidationMode::Quarantine(quarantine_path.to_string()), ..Validation::default() };
+    loaders[0].load_graph(&LoadOptions { validation: quarantine, ..options.clone() }).unwrap();
+    let quarantined = std::fs::read_to_string(quarantine_path).unwrap();
+    for path in [edges_path, jsonl_path, quarantine_path] {
+        std::fs::remove_file(path).expect("Failed to delete test file");
+    }
+
+    for loaded in loaded {
+        let (graph, summary) = loaded.unwrap();
+        assert_eq!((summary.rows_read, summary.accepted, summary.rejected_count()), (5, 2, 3));
+        for reason in [RejectReason::InvalidAddress, RejectReason::InvalidValue, RejectReason::SelfTransfer] {
+            assert_eq!(summary.rejected[&reason], 1, "{}", reason);
+        }
+        assert_eq!(graph.transfer_count(), 2);
+    }
+    // Without validation every parsable row is kept, as for the transfer csv
+    assert_eq!(unchecked.unwrap().1.accepted, 5);
+    let lines: Vec<&str> = quarantined.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].ends_with("last_timestamp,row,reason"));
+    assert!(lines[3].starts_with(&format!("{a},{a},unknown,1,5,5,5,1651104400,1651104400,4,self_transfer")));
+}
+
+#[test]
+fn test_transfer_csv_loader_summary() {
+    let a = "0x1111111111111111111111111111111111111111";
+    let b = "0x2222222222222222222222222222222222222222";
+    let path = "test_graph_loader_transfers.csv";
+    std::fs::write(
+        path,
+        format!(
+            "block_number,index,from_address,to_address,time_stamp,contract_address,value\n\
+             1,0,{a},{b},1651104100,USDT,50.0\n\
+             2,0,addr1,{b},1651104200,USDT,50.0\n\
+             3,0,{a},{b},1651104300,USDT,-5.0\n\
+             4,0,{a},{a},1651104400,USDT,5.0\n\
+             5,0,{b},{a},1651104500,USDT,10.0\n"
+        ),
+    )
+    .unwrap();
+    let loader = TransferCsvLoader { path: path.to_string() };
+    let options = LoadOptions {
+        progress: false,
+        validation: Validation { mode: ValidationMode::Skip, ..Validation::default() },
+        ..LoadOptions::default()
+    };
+    let loaded = loader.load_graph(&options);
+    // A visit error stops the stream at that row
+    let mut visited = 0;
+    let stopped = loader.read_flows(&options, &mut |_, _| {
+        visited += 1;
+        Err("stop".into())
+    });
+    std::fs::remove_file(path).expect("Failed to delete test file");
+
+    let (graph, summary) = loaded.unwrap();
+    assert_eq!((summary.rows_read, summary.accepted, summary.rejected_count()), (5, 2, 3));
+    for reason in [RejectReason::InvalidAddress, RejectReason::InvalidValue, RejectReason::SelfTransfer] {
+        assert_eq!(summary.rejected[&reason], 1, "{}", reason);
+    }
+    assert_eq!(graph.transfer_count(), 2);
+    assert_eq!(stopped.unwrap_err().to_string(), "stop");
+    assert_eq!(visited, 1);
+}
+
+#[cfg(feature = "parquet")]
+#[test]
+fn test_parquet_loader() {
+    use std::sync::Arc;
+    use parquet::data_type::{ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    let path = "test_graph_loader.parquet";
+    let schema = parse_message_type(
+        "message transfers {
+            REQUIRED BINARY from_address (UTF8);
+            REQUIRED BINARY to_address (UTF8);
+            REQUIRED BINARY contract_address (UTF8);
+            REQUIRED DOUBLE value;
+            REQUIRED INT64 time_stamp (TIMESTAMP_MILLIS);
+        }",
+    )
+    .unwrap();
+    let mut writer = SerializedFileWriter::new(File::create(path).unwrap(), Arc::new(schema), Default::default()).unwrap();
+    let mut row_group = writer.next_row_group().unwrap();
+    let text = |values: [&str; 2]| values.map(ByteArray::from);
+    let usdt = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+    let mut index = 0;
+    while let Some(mut column) = row_group.next_column().unwrap() {
+        match index {
+            0 => column.typed::<ByteArrayType>().write_batch(&text(["0xa", "0xb"]), None, None).unwrap(),
+            1 => column.typed::<ByteArrayType>().write_batch(&text(["0xb", "0xc"]), None, None).unwrap(),
+            2 => column.typed::<ByteArrayType>().write_batch(&text([usdt, usdt]), None, None).unwrap(),
+            3 => column.typed::<DoubleType>().write_batch(&[2_000_000.0, 500_000.0], None, None).unwrap(),
+            _ => column.typed::<Int64Type>().write_batch(&[1_651_104_000_000, 1_651_708_800_500], None, None).unwrap(),
+        };
+        column.close().unwrap();
+        index += 1;
+    }
+    row_group.close().unwrap();
+    writer.close().unwrap();
+
+    let loader = loader_for(InputFormat::from_path(path), path).unwrap();
+    let options = LoadOptions { normalize_values: true, ..LoadOptions::default() };
+    let (graphs, summary) = loader.load_periods(&PeriodSpec::luna_crash(), &options).unwrap();
+    std::fs::remove_file(path).expect("Failed to delete test file");
+
+    assert_eq!(summary.accepted, 2);
+    let before = &graphs["before"];
+    let edge = before.edge(before.node_id("0xa").unwrap(), before.node_id("0xb").unwrap()).unwrap();
+    assert_eq!((edge.total_value, edge.first_timestamp), (2.0, 1651104000));
+    assert_eq!(before.token_symbol(0), "USDT");
+    assert_eq!(graphs["during"].edge_count(), 1);
+}
@@ -0,0 +1,158 @@
+use std::path::Path;
+use crate::data_cleaning_load::data_loader::{PeriodGraphs, Transaction};
+use crate::graph::transaction_graph::{GraphBuilder, EdgeCounting};
+use crate::computation_analysis::computation_algorithms::{summarize_graphs, centrality_reports, CentralityOptions};
+use crate::connected_component::find_connected_component::component_reports;
+use crate::computation_analysis::token_analysis::compare_tokens;
+use crate::reporting::analysis_report::{AnalysisReport, OutputFormat};
+use crate::reporting::graph_export::{export_graphs_with, node_attributes, select_nodes, write_dot, write_graphml, ExportOptions, GraphFormat, Subgraph};
+
+fn report() -> AnalysisReport {
+    let mut builder = GraphBuilder::new();
+    for (from, to) in [("A", "B"), ("B", "C"), ("C", "A"), ("D", "E")] {
+        builder.add_transaction(from, to, &Transaction::new(10.0, "USDT".to_string(), 0));
+    }
+    let mut graphs = PeriodGraphs::new();
+    graphs.insert("before".to_string(), builder.build());
+    AnalysisReport {
+        graphs: summarize_graphs(&graphs, EdgeCounting::Distinct, true),
+        centrality: centrality_reports(&graphs, &CentralityOptions::default()),
+        components: component_reports(&graphs, 2),
+        tokens: compare_tokens(&graphs, &[], EdgeCounting::Distinct),
+        ..AnalysisReport::default()
+    }
+}
+
+//Test for the tidy rows of analysis_report module
+#[test]
+fn test_report_tidy_rows() {
+    let report = report();
+    let metric = |name: &str| report.metric_rows().into_iter().find(|row| row.metric == name).map(|row| (row.period, row.value));
+    assert_eq!(metric("nodes"), Some(("before".to_string(), 5.0)));
+    assert_eq!(metric("density"), Some(("before".to_string(), 4.0 / 20.0)));
+    assert_eq!(metric("largest_weak_component"), Some(("before".to_string(), 3.0)));
+
+    let distributions = report.distribution_rows();
+    let total_degree: Vec<(String, usize)> = distributions
+        .iter()
+        .filter(|row| row.distribution == "total_degree")
+        .map(|row| (row.bin.clone(), row.count))
+        .collect();
+    assert_eq!(total_degree, vec![("1".to_string(), 2), ("2".to_string(), 3)]);
+    assert_eq!(report.centrality[0].nodes.len(), 5);
+}
+
+//Test for the JSON and CSV renderers of analysis_report module
+#[test]
+fn test_report_writes_json_and_csv() {
+    let dir = Path::new("test_report_output");
+    let written = report().render(&[OutputFormat::Json, OutputFormat::Csv], dir);
+    let json = std::fs::read_to_string(dir.join("report.json"));
+    let centrality = std::fs::read_to_string(dir.join("centrality.csv"));
+    let metrics = std::fs::read_to_string(dir.join("metrics.csv"));
+    let tokens = std::fs::read_to_string(dir.join("tokens.csv"));
+    std::fs::remove_dir_all(dir).expect("Failed to delete test output directory");
+
+    assert_eq!(written.unwrap().len(), 6);
+    let json: serde_json::Value = serde_json::from_str(&json.unwrap()).unwrap();
+    assert_eq!(json["graphs"][0]["nodes"]["total"], 5);
+    assert_eq!(json["components"][0]["weak"]["size_distribution"]["3"], 1);
+    assert!(json.get("time_series").is_none());
+    let centrality = centrality.unwrap();
+    assert!(centrality.starts_with("period,address,degree,closeness,betweenness\n"));
+    assert_eq!(centrality.lines().count(), 6);
+    assert!(metrics.unwrap().starts_with("period,metric,value\nbefore,nodes,5"));
+    // The per-token comparison is part of the report like every other analysis
+    assert_eq!(json["tokens"][0]["token"], "USDT");
+    assert_eq!(json["tokens"][0]["stats"]["transfers"], 4);
+    let tokens = tokens.unwrap();
+    assert!(tokens.starts_with("period,token,symbol,nodes,edges,transfers,volume,"));
+    assert!(tokens.contains("\nbefore,USDT,USDT,5,4,4,40,"));
+}
+
+//Test for the graph_export module
+fn hub_graph() -> crate::graph::transaction_graph::TransactionGraph {
+    let mut builder = GraphBuilder::new();
+    // H pays three addresses, X -> Y is a separate pair, one address has a '&' in its label
+    for (from, to) in [("H", "A"), ("H", "B"), ("H", "C&D"), ("A", "B"), ("X", "Y")] {
+        builder.add_transaction(from, to, &Transaction::new(5.0, "USDT".to_string(), 7));
+    }
+    builder.build()
+}
+
+#[test]
+fn test_export_subgraphs_and_attributes() {
+    let graph = hub_graph();
+    let attributes = node_attributes(&graph, EdgeCounting::Distinct, None);
+    let h = graph.node_id("H").unwrap() as usize;
+    assert_eq!((attributes[h].out_degree, attributes[h].in_degree), (3, 0));
+    assert_eq!(attributes[h].degree_centrality, 3.0 / 5.0);
+    assert_eq!(attributes[h].closeness, None);
+
+    let names = |kept: Vec<bool>| -> Vec<String> {
+        graph.nodes().filter(|&node| kept[node as usize]).map(|node| graph.address(node).to_string()).collect()
+    };
+    assert_eq!(names(select_nodes(&graph, &attributes, Subgraph::LargestComponent)), vec!["H", "A", "B", "C&D"]);
+    assert_eq!(names(select_nodes(&graph, &attributes, Subgraph::Hubs(1))), vec!["H", "A", "B", "C&D"]);
+    assert_eq!("hubs:2".parse::<Subgraph>(), Ok(Subgraph::Hubs(2)));
+    assert!("hubs:many".parse::<Subgraph>().is_err());
+
+    let kept = select_nodes(&graph, &attributes, Subgraph::Full);
+    let mut graphml = Vec::new();
+    write_graphml(&graph, "before", &attributes, &kept, &mut graphml).unwrap();
+    let graphml = String::from_utf8(graphml).unwrap();
+    assert_eq!(graphml.matches("<node ").count(), 6);
+    assert_eq!(graphml.matches("<edge ").count(), 5);
+    assert!(graphml.contains(r#"<data key="label">C&amp;D</data>"#));
+    assert!(graphml.contains(r#"<data key="token">USDT</data>"#));
+
+    let mut dot = Vec::new();
+    write_dot(&graph, "before", &attributes, &select_nodes(&graph, &attributes, Subgraph::LargestComponent), &mut dot).unwrap();
+    let dot = String::from_utf8(dot).unwrap();
+    assert!(dot.starts_with("digraph \"before\" {"));
+    assert_eq!(dot.matches(" -> ").count(), 4);
+    assert!(dot.contains(
+        "value=5, transfers=1, token=\"USDT\", symbol=\"USDT\", min_value=5, max_value=5, first_timestamp=7, last_timestamp=7"
+    ));
+}
+
+#[test]
+fn test_export_writes_every_format() {
+    let dir = Path::new("test_graph_export_output");
+    let mut graphs = PeriodGraphs::new();
+    graphs.insert("before/USDT".to_string(), hub_graph());
+    let options = ExportOptions {
+        formats: vec![GraphFormat::EdgeList, GraphFormat::GraphMl, GraphFormat::Gexf, GraphFormat::Dot],
+        subgraph: Subgraph::Hubs(1),
+        centrality: Some(CentralityOptions::default()),
+        ..ExportOptions::default()
+    };
+    let written = export_graphs_with(&graphs, dir, &options).expect("Failed to export the graphs");
+    let names: Vec<String> = written.iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+    let gexf = std::fs::read_to_string(dir.join("before_USDT.hubs1.gexf")).unwrap();
+    std::fs::remove_dir_all(dir).expect("Failed to delete test output");
+
+    assert_eq!(
+        names,
+        vec!["before_USDT.hubs1.edges.csv", "before_USDT.hubs1.graphml", "before_USDT.hubs1.gexf", "before_USDT.hubs1.dot"]
+    );
+    assert!(gexf.contains(r#"<attribute id="4" title="betweenness" type="double"/>"#));
+    assert_eq!(gexf.matches("<node ").count(), 4);
+    assert!(gexf.contains(r#"weight="5""#));
+}
+
+#[test]
+fn test_export_keeps_periods_with_the_same_file_stem_apart() {
+    let dir = Path::new("test_graph_export_stems");
+    let mut graphs = PeriodGraphs::new();
+    graphs.insert("before/USDT".to_string(), hub_graph());
+    graphs.insert("before_USDT".to_string(), hub_graph());
+    graphs.insert("before USDT".to_string(), hub_graph());
+    let written = export_graphs_with(&graphs, dir, &ExportOptions::default()).expect("Failed to export the graphs");
+    let names: Vec<String> = written.iter().map(|path| path.file_name().unwrap().to_string_lossy().into_owned()).collect();
+    let exists = written.iter().all(|path| path.exists());
+    std::fs::remove_dir_all(dir).expect("Failed to delete test output");
+
+    assert_eq!(names, vec!["before_USDT.edges.csv", "before_USDT-2.edges.csv", "before_USDT-3.edges.csv"]);
+    assert!(exists);
+}
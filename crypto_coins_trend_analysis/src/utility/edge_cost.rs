@@ -0,0 +1,158 @@
+use crate::graph::transaction_graph::{TransactionGraph, NodeId, EdgeId, Edge, EdgeCounting};
+
+//EdgeCost, MIN_COST_FRACTION, EdgeWeights
+
+/**************************************************************
+*
+*   The named presets for the cost of following one edge in the
+*   shortest path algorithms, a cheaper edge means the money flows
+*   more tightly between the two addresses:
+*   Value: the transferred value itself (the original distance),
+*   a large transfer is "farther" than a small one. A pair of
+*   addresses that only exchanged zero-value transfers, mostly
+*   address-poisoning spam, moved no money and is not a path
+*   Hops: every edge costs 1, paths are counted in hops
+*   InverseValue: 1 / value, large transfers are short edges
+*   NegLogShare: -ln(share of the sender's total outflow sent on
+*   this edge), summed over a path it is -ln of the product of the
+*   shares, i.e. the fraction of the money that followed the path
+*   TimeDelay: seconds between the sender first receiving funds and
+*   its first transfer on this edge, 0 for addresses that never
+*   received anything in the graph
+*   With EdgeCounting::Transfers every transfer is a parallel edge,
+*   so the cheapest single transfer defines the cost
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EdgeCost {
+    #[default]
+    Value,
+    Hops,
+    InverseValue,
+    NegLogShare,
+    TimeDelay,
+}
+
+impl std::str::FromStr for EdgeCost {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "value" => Ok(EdgeCost::Value),
+            "hops" => Ok(EdgeCost::Hops),
+            "inverse-value" => Ok(EdgeCost::InverseValue),
+            "neg-log-share" => Ok(EdgeCost::NegLogShare),
+            "time-delay" => Ok(EdgeCost::TimeDelay),
+            _ => Err(format!(
+                "unknown edge cost '{}', expected value, hops, inverse-value, neg-log-share or time-delay",
+                value
+            )),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The smallest cost of an edge as a fraction of the cheapest
+*   positive cost of the graph. Forwarding the whole outflow
+*   (neg-log-share), forwarding in the same second (time-delay) or
+*   a zero cost of a custom function would cost nothing, and
+*   zero-cost edges make addresses tie in distance with their own
+*   predecessors, which breaks the path counting of weighted
+*   betweenness and puts reachable addresses at distance 0. Every
+*   cost below the floor is raised to it, so such an edge is the
+*   shortest of the graph but still a step, in the unit of the
+*   other costs whether they are token amounts, USD or seconds
+*
+***************************************************************/
+
+pub const MIN_COST_FRACTION: f64 = 1e-3;
+
+/**************************************************************
+*
+*   The cost of every edge of one graph indexed by EdgeId, computed
+*   once before the shortest path searches. Any cost function can be
+*   plugged in with from_fn, the presets are built by new. Costs
+*   below min_cost are raised to it, an infinite or NaN cost makes
+*   the edge unusable. min_cost is MIN_COST_FRACTION of the
+*   cheapest positive cost, or 1 when no cost is positive
+*
+***************************************************************/
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdgeWeights {
+    weights: Vec<f64>,
+    min_cost: f64,
+}
+
+impl EdgeWeights {
+    pub fn from_fn(graph: &TransactionGraph, cost: impl Fn(NodeId, NodeId, &Edge) -> f64) -> Self {
+        let costs: Vec<f64> = graph
+            .nodes()
+            .flat_map(|node| graph.out_edge_ids(node))
+            .map(|edge| cost(graph.edge_source(edge), graph.edge_target(edge), graph.edge_by_id(edge)))
+            .collect();
+        let cheapest = costs.iter().copied().filter(|&cost| cost > 0.0 && cost.is_finite()).min_by(f64::total_cmp);
+        let min_cost = cheapest.map_or(1.0, |cheapest| cheapest * MIN_COST_FRACTION);
+        let weights = costs
+            .into_iter()
+            .map(|cost| if cost.is_nan() { f64::INFINITY } else { cost.max(min_cost) })
+            .collect();
+        EdgeWeights { weights, min_cost }
+    }
+
+    pub fn new(graph: &TransactionGraph, cost: EdgeCost, counting: EdgeCounting) -> Self {
+        match cost {
+            EdgeCost::Value => EdgeWeights::from_fn(graph, |_, _, edge| match counting {
+                _ if edge.total_value <= 0.0 => f64::INFINITY,
+                EdgeCounting::Distinct => edge.total_value,
+                EdgeCounting::Transfers => edge.min_value,
+            }),
+            EdgeCost::Hops => EdgeWeights::from_fn(graph, |_, _, _| 1.0),
+            EdgeCost::InverseValue => EdgeWeights::from_fn(graph, |_, _, edge| 1.0 / edge_value(edge, counting)),
+            EdgeCost::NegLogShare => {
+                let outflow: Vec<f64> = graph
+                    .nodes()
+                    .map(|node| graph.out_edges(node).map(|(_, edge)| edge.total_value).sum())
+                    .collect();
+                EdgeWeights::from_fn(graph, |source, _, edge| {
+                    let share = edge_value(edge, counting) / outflow[source as usize];
+                    if share > 0.0 { -share.min(1.0).ln() } else { f64::INFINITY }
+                })
+            }
+            EdgeCost::TimeDelay => {
+                let first_received: Vec<Option<u64>> = graph
+                    .nodes()
+                    .map(|node| graph.in_edges(node).map(|(_, edge)| edge.first_timestamp).min())
+                    .collect();
+                EdgeWeights::from_fn(graph, |source, _, edge| match first_received[source as usize] {
+                    Some(received) => edge.first_timestamp.saturating_sub(received) as f64,
+                    None => 0.0,
+                })
+            }
+        }
+    }
+
+    pub fn weight(&self, edge: EdgeId) -> f64 {
+        self.weights[edge as usize]
+    }
+
+    pub fn min_cost(&self) -> f64 {
+        self.min_cost
+    }
+}
+
+/**************************************************************
+*
+*   Helper function for the value based costs, the cheapest single
+*   transfer is the largest one when every transfer is an edge
+*
+***************************************************************/
+
+fn edge_value(edge: &Edge, counting: EdgeCounting) -> f64 {
+    match counting {
+        EdgeCounting::Distinct => edge.total_value,
+        EdgeCounting::Transfers => edge.max_value,
+    }
+}
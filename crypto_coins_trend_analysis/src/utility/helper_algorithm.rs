@@ -1,67 +1,201 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::collections::BinaryHeap;
-use crate::data_loader::Transaction;
-
-//dijkstra
-/**************************************************************
-*
-*   The datastructure Distance represent teh distance of graph,
-*   which also represent the weight value, but since weight is
-*   f64, could not directly used in Binary Heap, so defined a
-*   new datastructure that implements tha required traits for
-*   Binary Heap
-*
-***************************************************************/
-#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
-pub struct Distance(pub f64);
-
-impl Eq for Distance {}
-
-impl Ord for Distance {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap_or(std::cmp::Ordering::Equal)
-    }
-}
-
-/**************************************************************
-*
-*   The helper function dijkstra algorithm for calculation of
-*   shortest path in the weighted graph(non-negative weight),
-*   implemented to fit the defined graph in this project
-*
-***************************************************************/
-
-pub fn dijkstra(
-    graph: &HashMap<String, HashMap<String, Transaction>>,
-    start: &str,
-) -> HashMap<String, f64> {
-    let mut distances: HashMap<String, Distance> = HashMap::new();
-    let mut visited: HashSet<String> = HashSet::new();
-    let mut heap = BinaryHeap::new();
-
-    distances.insert(start.to_string(), Distance(0.0));
-    heap.push(std::cmp::Reverse((Distance(0.0), start.to_string())));
-
-    while let Some(std::cmp::Reverse((dist, current))) = heap.pop() {
-        if !visited.insert(current.clone()) {
-            continue;
-        }
-
-        if let Some(neighbors) = graph.get(&current) {
-            for (neighbor, transaction) in neighbors {
-                let new_dist = Distance(dist.0 + transaction.value);
-                let is_shorter = distances
-                    .get(neighbor)
-                    .map_or(true, |&current_dist| new_dist < current_dist);
-
-                if is_shorter {
-                    distances.insert(neighbor.clone(), new_dist);
-                    heap.push(std::cmp::Reverse((new_dist, neighbor.clone())));
-                }
-            }
-        }
-    }
-
-    distances.into_iter().map(|(k, v)| (k, v.0)).collect()
-}
\ No newline at end of file
+use std::collections::BinaryHeap;
+use crate::graph::transaction_graph::{TransactionGraph, NodeId, EdgeId};
+use crate::utility::edge_cost::EdgeWeights;
+
+//dijkstra, dijkstra_directed, shortest_path_tree, shortest_path, trace_path, with_thread_pool
+/**************************************************************
+*
+*   The datastructure Distance represent teh distance of graph,
+*   which also represent the weight value, but since weight is
+*   f64, could not directly used in Binary Heap, so defined a
+*   new datastructure that implements tha required traits for
+*   Binary Heap
+*
+***************************************************************/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distance(pub f64);
+
+impl Eq for Distance {}
+
+impl PartialOrd for Distance {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Distance {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(std::cmp::Ordering::Equal)
+    }
+}
+
+/**************************************************************
+*
+*   The direction the paths are followed in, Outgoing follows the
+*   money from the start node, Incoming walks the edges backwards
+*   to find the distance from every other node to the start node
+*
+***************************************************************/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathDirection {
+    #[default]
+    Outgoing,
+    Incoming,
+}
+
+impl std::str::FromStr for PathDirection {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "out" | "outgoing" => Ok(PathDirection::Outgoing),
+            "in" | "incoming" => Ok(PathDirection::Incoming),
+            _ => Err(format!("unknown path direction '{}', expected out or in", value)),
+        }
+    }
+}
+
+/**************************************************************
+*
+*   The helper function dijkstra algorithm for calculation of
+*   shortest path in the weighted graph(non-negative weight),
+*   implemented to fit the defined graph in this project.
+*   The cost of every edge is looked up in the given EdgeWeights,
+*   see EdgeCost for the presets. The result is the distance to
+*   every node indexed by NodeId, unreachable nodes are at
+*   f64::INFINITY
+*
+***************************************************************/
+
+pub fn dijkstra(
+    graph: &TransactionGraph,
+    start: NodeId,
+    weights: &EdgeWeights,
+) -> Vec<f64> {
+    dijkstra_directed(graph, start, weights, PathDirection::Outgoing)
+}
+
+/**************************************************************
+*
+*   The dijkstra algorithm following the edges in the given
+*   direction, with PathDirection::Incoming the result is the
+*   distance from every node to the start node
+*
+***************************************************************/
+
+pub fn dijkstra_directed(
+    graph: &TransactionGraph,
+    start: NodeId,
+    weights: &EdgeWeights,
+    direction: PathDirection,
+) -> Vec<f64> {
+    shortest_path_tree(graph, start, weights, direction).0
+}
+
+/**************************************************************
+*
+*   The dijkstra algorithm that also records the tree of the
+*   shortest paths, the predecessor of every reached node on its
+*   shortest path from the start (its successor towards the start
+*   with PathDirection::Incoming), None for the start and the
+*   unreachable nodes
+*
+***************************************************************/
+
+pub fn shortest_path_tree(
+    graph: &TransactionGraph,
+    start: NodeId,
+    weights: &EdgeWeights,
+    direction: PathDirection,
+) -> (Vec<f64>, Vec<Option<NodeId>>) {
+    let mut distances: Vec<Distance> = vec![Distance(f64::INFINITY); graph.node_count()];
+    let mut predecessors: Vec<Option<NodeId>> = vec![None; graph.node_count()];
+    let mut visited: Vec<bool> = vec![false; graph.node_count()];
+    let mut heap = BinaryHeap::new();
+
+    distances[start as usize] = Distance(0.0);
+    heap.push(std::cmp::Reverse((Distance(0.0), start)));
+
+    while let Some(std::cmp::Reverse((dist, current))) = heap.pop() {
+        if visited[current as usize] {
+            continue;
+        }
+        visited[current as usize] = true;
+
+        let mut relax = |neighbor: NodeId, edge: EdgeId| {
+            let new_dist = Distance(dist.0 + weights.weight(edge));
+
+            if new_dist < distances[neighbor as usize] {
+                distances[neighbor as usize] = new_dist;
+                predecessors[neighbor as usize] = Some(current);
+                heap.push(std::cmp::Reverse((new_dist, neighbor)));
+            }
+        };
+        match direction {
+            PathDirection::Outgoing => graph.out_edge_ids(current).for_each(|edge| relax(graph.edge_target(edge), edge)),
+            PathDirection::Incoming => graph.in_edge_ids(current).for_each(|edge| relax(graph.edge_source(edge), edge)),
+        }
+    }
+
+    (distances.into_iter().map(|distance| distance.0).collect(), predecessors)
+}
+
+/**************************************************************
+*
+*   The shortest path from start to target along the transfers,
+*   its cost and the nodes from start to target, None when the
+*   target could not be reached
+*
+***************************************************************/
+
+pub fn shortest_path(
+    graph: &TransactionGraph,
+    start: NodeId,
+    target: NodeId,
+    weights: &EdgeWeights,
+) -> Option<(f64, Vec<NodeId>)> {
+    let (distances, predecessors) = shortest_path_tree(graph, start, weights, PathDirection::Outgoing);
+    if !distances[target as usize].is_finite() {
+        return None;
+    }
+    Some((distances[target as usize], trace_path(&predecessors, target)))
+}
+
+/**************************************************************
+*
+*   Follow the predecessors of a shortest path tree back from the
+*   target, the nodes of its path from the start to the target
+*
+***************************************************************/
+
+pub fn trace_path(predecessors: &[Option<NodeId>], target: NodeId) -> Vec<NodeId> {
+    let mut path = vec![target];
+    while let Some(previous) = predecessors[*path.last().unwrap() as usize] {
+        path.push(previous);
+    }
+    path.reverse();
+    path
+}
+
+/**************************************************************
+*
+*   Run the given computation on a rayon thread pool with the
+*   given number of threads, or on the global pool that uses all
+*   cores when no thread count is given
+*
+***************************************************************/
+
+pub fn with_thread_pool<R: Send>(threads: Option<usize>, computation: impl FnOnce() -> R + Send) -> R {
+    let pool = match threads {
+        Some(threads) => rayon::ThreadPoolBuilder::new().num_threads(threads).build(),
+        None => return computation(),
+    };
+    match pool {
+        Ok(pool) => pool.install(computation),
+        Err(e) => {
+            eprintln!("Could not create a pool of {:?} threads, using all cores: {}", threads, e);
+            computation()
+        }
+    }
+}